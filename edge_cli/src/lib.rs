@@ -0,0 +1,1802 @@
+use edgelib::model::FastCombinations;
+use edgelib::model::Location;
+use edgelib::model::MomentCombinatorics;
+use edgelib::model::PuzzleCombinations;
+use edgelib::model::SearchNodes;
+use edgelib::model::distinct_transforms;
+use edgelib::model::{BoardShape, parse_json_locations, parse_locations};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use edgelib::cube::{CubeCombinations, CubeOrder, CubeProgress, CubeSearchOption, CubeStructure};
+use edgelib::instance::{GeneratedPuzzle, InstanceCombinations, PuzzleInstance};
+use edgelib::model::Lattice;
+use edgelib::model::LocationType;
+use edgelib::model::Orientations;
+use edgelib::model::PieceClass;
+use edgelib::model::PuzzleStructure;
+use edgelib::model::SearchOrder;
+use edgelib::model::SearchOrderStrategy;
+use edgelib::model::SearchProgress;
+use edgelib::model::SearchRegistry;
+use edgelib::model::SearchSpread;
+use edgelib::model::SearchSummary;
+use edgelib::model::Topology;
+use edgelib::optimiser::{ExactOptimiser, Objective, OptimiserMethod, OrderOptimiser};
+use edgelib::solver::{BacktrackSolver, CONFIDENCE_95};
+use edgelib::sweep::{ParameterSweep, SweepResult, critical_points};
+use serde::Serialize;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+#[derive(Parser, Debug)]
+#[command(name = "Edge Puzzle CLI")]
+#[command(version = "1.0")]
+#[command(about = "Generates information about edge puzzles", long_about = None)]
+#[command(author = "Brendan Owen")]
+struct CliArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Args, Debug)]
+struct PuzzleArgs {
+    /// Width of puzzle
+    #[arg(
+        short,
+        long,
+        value_name = "INTEGER",
+        required_unless_present_any = ["pieces", "shape"]
+    )]
+    x: Option<usize>,
+
+    /// Height of puzzle
+    #[arg(
+        short,
+        long,
+        value_name = "INTEGER",
+        required_unless_present_any = ["pieces", "shape"]
+    )]
+    y: Option<usize>,
+
+    /// Border edge types
+    #[arg(
+        short,
+        long,
+        value_name = "INTEGER",
+        required_unless_present = "pieces"
+    )]
+    border: Option<usize>,
+
+    /// Middle edge types
+    #[arg(
+        short,
+        long,
+        value_name = "INTEGER",
+        required_unless_present = "pieces"
+    )]
+    middle: Option<usize>,
+
+    /// Comma-delimited list of border join counts.
+    #[arg(long, value_name = "INTEGERS", num_args = 1.., value_delimiter = ',')]
+    border_joins: Option<Vec<usize>>,
+
+    /// Comma-delimited list of middle join counts.
+    #[arg(long, value_name = "INTEGERS", num_args = 1.., value_delimiter = ',')]
+    middle_joins: Option<Vec<usize>>,
+
+    /// Piece file to take the size, edge types, join counts and hints from.
+    #[arg(long, value_name = "PIECE FILE", conflicts_with_all = ["x", "y", "border", "middle"])]
+    pieces: Option<String>,
+
+    /// File giving the cells of an irregular board as ASCII art or a list of x,y cells.
+    #[arg(long, value_name = "SHAPE FILE", conflicts_with_all = ["x", "y"])]
+    shape: Option<String>,
+
+    /// Which sides of the board wrap round to meet the opposite side.
+    #[arg(long, value_enum, default_value_t = TopologyType::Plane, conflicts_with = "pieces")]
+    topology: TopologyType,
+
+    /// Shape of the pieces. Odd rows of hexagons sit half a cell to the right and
+    /// triangles alternate pointing up and down.
+    #[arg(long, value_enum, default_value_t = LatticeType::Square, conflicts_with = "pieces")]
+    lattice: LatticeType,
+
+    /// How pieces can be laid: turned, fixed, turned and flipped over, or as
+    /// rectangular tiles that fit either way round.
+    #[arg(long, value_enum, default_value_t = OrientationType::Rotating)]
+    orientations: OrientationType,
+
+    /// Cell whose piece is placed before searching. Repeat for each hint.
+    #[arg(long, value_name = "X,Y", value_parser = parse_location)]
+    hint: Vec<Location>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Lists the search orders that can be named in --searches
+    Searches,
+
+    /// Outputs known search profile
+    Profile {
+        #[command(flatten)]
+        puzzle: PuzzleArgs,
+
+        /// Comma-delimited list of search orders to calculate.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// File listing a search order as x,y cells, in CSV or JSON. Repeat for each file.
+        #[arg(long, value_name = "ORDER FILE")]
+        order_file: Vec<String>,
+
+        /// Output ranked summary metrics for each order instead of the profile.
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        summary: Option<SummaryFormat>,
+
+        /// Work out the profile from exact ratios instead of f64 sums.
+        #[arg(long)]
+        exact: bool,
+
+        /// Score each depth with the colours on the pieces of the piece file.
+        /// Hints come from the fixed pieces in the file, so --hint is not allowed.
+        #[arg(long, requires = "pieces", conflicts_with_all = ["exact", "hint"])]
+        instance: bool,
+
+        /// Also profile each named order turned and flipped every way, leaving
+        /// out those that fill the board the same as one already listed.
+        #[arg(long)]
+        transforms: bool,
+
+        /// Add the counts one standard deviation either side of each order
+        /// across random puzzles, and a flag for depths driven by rare puzzles.
+        /// With --exact the second moment comes from solving every colouring
+        /// of the board, which only small boards allow. Otherwise it is
+        /// approximate, so treat it as a guide.
+        #[arg(long, conflicts_with_all = ["instance", "summary"])]
+        spread: bool,
+
+        /// Output search profile. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Outputs the combinations pieces and probability profile of joins
+    Probability {
+        #[command(flatten)]
+        puzzle: PuzzleArgs,
+
+        /// Work out the values from exact ratios instead of f64 sums.
+        #[arg(long)]
+        exact: bool,
+
+        /// Output search profile. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Reports the largest gap between the f64 and exact values of each column
+    ComparePrecision {
+        #[command(flatten)]
+        puzzle: PuzzleArgs,
+
+        /// Comma-delimited list of search orders to compare.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// File listing a search order as x,y cells, in CSV or JSON. Repeat for each file.
+        #[arg(long, value_name = "ORDER FILE")]
+        order_file: Vec<String>,
+
+        /// Output largest difference per column. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE", default_value = "-")]
+        output: String,
+    },
+
+    /// Searches for the order with the fewest total expected nodes
+    Optimise {
+        #[command(flatten)]
+        puzzle: PuzzleArgs,
+
+        /// How to search the space of orders.
+        #[arg(long, value_enum, default_value_t = OptimiseMethod::Annealing)]
+        method: OptimiseMethod,
+
+        /// Partial orders kept at each depth by beam search.
+        #[arg(long, value_name = "INTEGER", default_value_t = 8)]
+        beam_width: usize,
+
+        /// Moves tried by simulated annealing.
+        #[arg(long, value_name = "INTEGER", default_value_t = 10000)]
+        iterations: usize,
+
+        /// Seed for the random number generator.
+        #[arg(long, value_name = "INTEGER", default_value_t = 0)]
+        seed: u64,
+
+        /// Only place cells next to a cell already placed.
+        #[arg(long)]
+        connected: bool,
+
+        /// File to write the best order to, for use with --order-file. Written as
+        /// a JSON list of [x, y] pairs if the name ends in .json, else as x,y CSV.
+        #[arg(long, value_name = "ORDER FILE")]
+        order_output: Option<String>,
+
+        /// Output search profile of the best order. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Finds the order with the fewest expected nodes exactly, for small puzzles
+    OptimalOrder {
+        #[command(flatten)]
+        puzzle: PuzzleArgs,
+
+        /// Whether to minimise the total or the peak expected nodes.
+        #[arg(long, value_enum, default_value_t = ObjectiveType::Total)]
+        objective: ObjectiveType,
+
+        /// Only place cells next to a cell already placed.
+        #[arg(long)]
+        connected: bool,
+
+        /// Give up after reaching this many sets of filled cells.
+        #[arg(long, value_name = "INTEGER", default_value_t = 20_000_000)]
+        max_states: usize,
+
+        /// File to write the optimal order to, for use with --order-file. Written
+        /// as a JSON list of [x, y] pairs if the name ends in .json, else as x,y CSV.
+        #[arg(long, value_name = "ORDER FILE")]
+        order_output: Option<String>,
+
+        /// Output search profile of the optimal order and every built-in order. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Solves a piece file and outputs the nodes visited at each depth
+    Solve {
+        /// Piece file with one piece per line as top, right, bottom and left colours.
+        #[arg(long, value_name = "PIECE FILE")]
+        pieces: String,
+
+        /// File giving the cells of the board the pieces fill, if it is not a full rectangle.
+        #[arg(long, value_name = "SHAPE FILE")]
+        shape: Option<String>,
+
+        /// Comma-delimited list of search orders to solve with.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// Stop each search after this many solutions.
+        #[arg(long, value_name = "INTEGER")]
+        max_solutions: Option<usize>,
+
+        /// Stop each search after visiting this many nodes.
+        #[arg(long, value_name = "INTEGER")]
+        max_nodes: Option<u64>,
+
+        /// Piece file to write the first solution to, with every piece fixed.
+        #[arg(long, value_name = "PIECE FILE")]
+        solution: Option<String>,
+
+        /// Output log10 nodes visited at each depth. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Estimates the nodes at each depth of the backtrack tree from random probes
+    Estimate {
+        /// Piece file with one piece per line as top, right, bottom and left colours.
+        #[arg(long, value_name = "PIECE FILE")]
+        pieces: String,
+
+        /// File giving the cells of the board the pieces fill, if it is not a full rectangle.
+        #[arg(long, value_name = "SHAPE FILE")]
+        shape: Option<String>,
+
+        /// Comma-delimited list of search orders to estimate.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// Number of probes for each order.
+        #[arg(long, value_name = "INTEGER", default_value_t = 1000)]
+        probes: usize,
+
+        /// Seed for the random number generator.
+        #[arg(long, value_name = "INTEGER", default_value_t = 0)]
+        seed: u64,
+
+        /// Take each child in proportion to its own children instead of uniformly.
+        #[arg(long)]
+        importance: bool,
+
+        /// Output log10 estimated nodes with 95% bounds at each depth. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Generates a random solvable puzzle and writes it as a piece file
+    Generate {
+        /// Width of puzzle
+        #[arg(short, long, value_name = "INTEGER")]
+        x: usize,
+
+        /// Height of puzzle
+        #[arg(short, long, value_name = "INTEGER")]
+        y: usize,
+
+        /// Border edge types
+        #[arg(short, long, value_name = "INTEGER")]
+        border: usize,
+
+        /// Middle edge types
+        #[arg(short, long, value_name = "INTEGER")]
+        middle: usize,
+
+        /// Comma-delimited list of border join counts.
+        #[arg(long, value_name = "INTEGERS", num_args = 1.., value_delimiter = ',')]
+        border_joins: Option<Vec<usize>>,
+
+        /// Comma-delimited list of middle join counts.
+        #[arg(long, value_name = "INTEGERS", num_args = 1.., value_delimiter = ',')]
+        middle_joins: Option<Vec<usize>>,
+
+        /// Seed for the random number generator.
+        #[arg(long, value_name = "INTEGER", default_value_t = 0)]
+        seed: u64,
+
+        /// Piece file to write the solution to, with every piece fixed.
+        #[arg(long, value_name = "PIECE FILE")]
+        solution: Option<String>,
+
+        /// Output piece file. "-" writes to stdout.
+        #[arg(short, long, value_name = "PIECE FILE")]
+        output: String,
+    },
+
+    /// Reports the puzzle parameters found in a piece file
+    Inspect {
+        /// Piece file with one piece per line as top, right, bottom and left colours.
+        #[arg(long, value_name = "PIECE FILE")]
+        pieces: String,
+
+        /// File giving the cells of the board the pieces fill, if it is not a full rectangle.
+        #[arg(long, value_name = "SHAPE FILE")]
+        shape: Option<String>,
+
+        /// Output puzzle parameters. "-" writes to stdout.
+        #[arg(short, long, value_name = "TEXT FILE", default_value = "-")]
+        output: String,
+    },
+
+    /// Outputs the search profile of cubes in a box
+    CubeProfile {
+        /// Width of the box
+        #[arg(short, long, value_name = "INTEGER")]
+        x: usize,
+
+        /// Height of the box
+        #[arg(short, long, value_name = "INTEGER")]
+        y: usize,
+
+        /// Depth of the box
+        #[arg(short, long, value_name = "INTEGER")]
+        z: usize,
+
+        /// Outer join types
+        #[arg(long, value_name = "INTEGER")]
+        outer: usize,
+
+        /// Inner join types
+        #[arg(long, value_name = "INTEGER")]
+        inner: usize,
+
+        /// Comma-delimited list of cube search orders to calculate.
+        #[arg(short, long, value_enum, num_args = 1.., value_delimiter = ',')]
+        searches: Vec<CubeSearchType>,
+
+        /// Output ranked summary metrics for each order instead of the profile.
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        summary: Option<SummaryFormat>,
+
+        /// Output search profile. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+
+    /// Outputs summary metrics of each search order across ranges of puzzle designs
+    Sweep {
+        /// Range of widths, as a single value or MIN-MAX.
+        #[arg(short, long, value_name = "MIN-MAX", value_parser = parse_range)]
+        x: RangeInclusive<usize>,
+
+        /// Range of heights, as a single value or MIN-MAX.
+        #[arg(short, long, value_name = "MIN-MAX", value_parser = parse_range)]
+        y: RangeInclusive<usize>,
+
+        /// Range of border edge types, as a single value or MIN-MAX.
+        #[arg(short, long, value_name = "MIN-MAX", value_parser = parse_range)]
+        border: RangeInclusive<usize>,
+
+        /// Range of middle edge types, as a single value or MIN-MAX.
+        #[arg(short, long, value_name = "MIN-MAX", value_parser = parse_range)]
+        middle: RangeInclusive<usize>,
+
+        /// Comma-delimited list of ratios between the join counts of each
+        /// edge type and the one before. 1 shares the joins out evenly.
+        #[arg(long, value_name = "NUMBERS", num_args = 1.., value_delimiter = ',', default_value = "1")]
+        skews: Vec<f64>,
+
+        /// Comma-delimited list of search orders to calculate.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// Threads to share the points between. Defaults to the available parallelism.
+        #[arg(long, value_name = "INTEGER")]
+        threads: Option<usize>,
+
+        #[arg(long, value_enum, default_value = "csv")]
+        format: SweepFormat,
+
+        /// Output sweep metrics. "-" writes to stdout.
+        #[arg(short, long, value_name = "FILE")]
+        output: String,
+    },
+
+    /// Finds the middle edge types where the expected solutions fall to a target
+    Critical {
+        /// Width of puzzle
+        #[arg(short, long, value_name = "INTEGER")]
+        x: usize,
+
+        /// Height of puzzle
+        #[arg(short, long, value_name = "INTEGER")]
+        y: usize,
+
+        /// Border edge types
+        #[arg(short, long, value_name = "INTEGER")]
+        border: usize,
+
+        /// Comma-delimited list of ratios between the join counts of each
+        /// middle edge type and the one before. 1 shares the joins out evenly.
+        #[arg(long, value_name = "NUMBERS", num_args = 1.., value_delimiter = ',', default_value = "1")]
+        skews: Vec<f64>,
+
+        /// Expected solutions to fall to.
+        #[arg(long, value_name = "NUMBER", default_value_t = 1.0)]
+        target: f64,
+
+        /// Comma-delimited list of search orders to report the total cost of.
+        #[arg(short, long, value_name = "NAMES", num_args = 1.., value_delimiter = ',')]
+        searches: Vec<String>,
+
+        /// Output critical points. "-" writes to stdout.
+        #[arg(short, long, value_name = "CSV FILE")]
+        output: String,
+    },
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum OptimiseMethod {
+    Greedy,
+    Beam,
+    Annealing,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum TopologyType {
+    Plane,
+    Cylinder,
+    Torus,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum LatticeType {
+    Square,
+    Hexagonal,
+    Triangular,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum OrientationType {
+    Rotating,
+    Fixed,
+    Flippable,
+    Rectangular,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum SummaryFormat {
+    Table,
+    Json,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum SweepFormat {
+    Csv,
+    Json,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum ObjectiveType {
+    Total,
+    Peak,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum CubeSearchType {
+    ScanLayers,
+    ShellIn,
+    ShellOut,
+    SurfaceFirst,
+    SurfaceLast,
+}
+
+impl From<CubeSearchType> for CubeSearchOption {
+    fn from(search_type: CubeSearchType) -> Self {
+        match search_type {
+            CubeSearchType::ScanLayers => CubeSearchOption::ScanLayers,
+            CubeSearchType::ShellIn => CubeSearchOption::ShellIn,
+            CubeSearchType::ShellOut => CubeSearchOption::ShellOut,
+            CubeSearchType::SurfaceFirst => CubeSearchOption::SurfaceFirst,
+            CubeSearchType::SurfaceLast => CubeSearchOption::SurfaceLast,
+        }
+    }
+}
+
+/// Runs the command line with the search orders of `registry`, so a crate
+/// that registers orders of its own can offer them through `--searches`.
+pub fn run(registry: &SearchRegistry) {
+    // Parse the command-line arguments
+    let args = CliArgs::parse();
+
+    match &args.command {
+        Commands::Searches => {
+            registry
+                .strategies()
+                .iter()
+                .for_each(|strategy| println!("{}", strategy.name()));
+            registry
+                .patterns()
+                .iter()
+                .for_each(|pattern| println!("{}", pattern));
+        }
+
+        Commands::Profile {
+            puzzle,
+            searches,
+            order_file,
+            summary,
+            exact,
+            instance,
+            transforms,
+            spread,
+            output,
+        } => {
+            let puzzle_structure: PuzzleStructure = puzzle.structure();
+
+            let mut strategies = strategies(registry, searches);
+            if *transforms {
+                strategies = strategies
+                    .iter()
+                    .flat_map(|strategy| {
+                        distinct_transforms(&puzzle_structure, strategy)
+                            .unwrap_or_else(|error| exit_with_error(&error))
+                    })
+                    .collect();
+            }
+            let (headers, search_orders) =
+                search_orders(&puzzle_structure, &strategies, order_file);
+            let search_progress: Vec<SearchProgress> = search_orders
+                .iter()
+                .map(|search_order| SearchProgress::new(&puzzle_structure, search_order))
+                .collect();
+            let instance_nodes: Option<Vec<SearchNodes>> = instance.then(|| {
+                let puzzle_instance = load_instance(puzzle.pieces.as_ref().unwrap());
+                let instance_combinations =
+                    InstanceCombinations::new(&puzzle_instance, &puzzle_structure)
+                        .unwrap_or_else(|error| exit_with_error(&error));
+                search_progress
+                    .iter()
+                    .map(|progress| SearchNodes::new(&instance_combinations, progress))
+                    .collect()
+            });
+
+            let puzzle_combinations: Box<dyn MomentCombinatorics> = if *exact {
+                Box::new(PuzzleCombinations::new(&puzzle_structure))
+            } else {
+                Box::new(FastCombinations::new(&puzzle_structure))
+            };
+            let search_nodes: Vec<SearchNodes> = if let Some(instance_nodes) = instance_nodes {
+                instance_nodes
+            } else {
+                search_progress
+                    .iter()
+                    .map(|search_progress| {
+                        SearchNodes::new(puzzle_combinations.as_ref(), search_progress)
+                    })
+                    .collect()
+            };
+
+            if let Some(summary) = summary {
+                let summaries: Vec<SearchSummary> =
+                    search_nodes.iter().map(SearchSummary::new).collect();
+                output_default_stdout(
+                    output,
+                    match summary {
+                        SummaryFormat::Table => summary_table(&headers, &summaries),
+                        SummaryFormat::Json => summary_json(&headers, &summaries),
+                    },
+                );
+                return;
+            }
+
+            if *spread {
+                let mut spread_headers: Vec<String> = vec![];
+                let mut columns: Vec<Vec<f64>> = vec![];
+                headers
+                    .iter()
+                    .zip(search_nodes.iter().zip(search_progress.iter()))
+                    .for_each(|(name, (search_nodes, search_progress))| {
+                        let search_spread =
+                            SearchSpread::new(puzzle_combinations.as_ref(), search_progress)
+                                .unwrap_or_else(|error| exit_with_error(&error));
+                        let (lower, upper) = search_spread.log10_bounds(search_nodes);
+                        let rare: Vec<f64> = search_spread
+                            .rare()
+                            .iter()
+                            .map(|rare| if *rare { 1.0 } else { 0.0 })
+                            .collect();
+                        spread_headers.extend([
+                            name.clone(),
+                            format!("{} Lower", name),
+                            format!("{} Upper", name),
+                            format!("{} Rare", name),
+                        ]);
+                        columns.extend([search_nodes.nodes.clone(), lower, upper, rare]);
+                    });
+                output_default_stdout(output, profile_csv(&spread_headers, &columns));
+                return;
+            }
+
+            let columns: Vec<Vec<f64>> = search_nodes
+                .into_iter()
+                .map(|search_node| search_node.nodes)
+                .collect();
+
+            output_default_stdout(output, profile_csv(&headers, &columns));
+        }
+
+        Commands::Probability {
+            puzzle,
+            exact,
+            output,
+        } => {
+            let puzzle_structure: PuzzleStructure = puzzle.structure();
+
+            let result_string = if *exact {
+                let puzzle_combinations = PuzzleCombinations::new(&puzzle_structure);
+                probability_csv(
+                    &probability_headers(&puzzle_structure),
+                    &probability_columns(
+                        &puzzle_combinations.log10_middle,
+                        &puzzle_combinations.log10_border,
+                        &puzzle_combinations.log10_pieces,
+                    ),
+                )
+            } else {
+                let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+                probability_csv(
+                    &probability_headers(&puzzle_structure),
+                    &probability_columns(
+                        &puzzle_combinations.log10_middle,
+                        &puzzle_combinations.log10_border,
+                        &puzzle_combinations.log10_pieces,
+                    ),
+                )
+            };
+
+            output_default_stdout(output, result_string);
+        }
+
+        Commands::ComparePrecision {
+            puzzle,
+            searches,
+            order_file,
+            output,
+        } => {
+            let puzzle_structure: PuzzleStructure = puzzle.structure();
+            let fast_combinations = FastCombinations::new(&puzzle_structure);
+            let exact_combinations = PuzzleCombinations::new(&puzzle_structure);
+
+            let mut headers: Vec<String> = probability_headers(&puzzle_structure);
+            let mut fast_columns: Vec<Vec<f64>> = probability_columns(
+                &fast_combinations.log10_middle,
+                &fast_combinations.log10_border,
+                &fast_combinations.log10_pieces,
+            );
+            let mut exact_columns: Vec<Vec<f64>> = probability_columns(
+                &exact_combinations.log10_middle,
+                &exact_combinations.log10_border,
+                &exact_combinations.log10_pieces,
+            );
+
+            let (search_headers, search_orders) = search_orders(
+                &puzzle_structure,
+                &strategies(registry, searches),
+                order_file,
+            );
+            headers.extend(search_headers);
+            search_orders.iter().for_each(|search_order| {
+                let search_progress = SearchProgress::new(&puzzle_structure, search_order);
+                fast_columns.push(SearchNodes::new(&fast_combinations, &search_progress).nodes);
+                exact_columns.push(SearchNodes::new(&exact_combinations, &search_progress).nodes);
+            });
+
+            let mut result_string = String::from("Column,Max Difference,Depth\n");
+            headers
+                .iter()
+                .zip(fast_columns.iter().zip(exact_columns.iter()))
+                .for_each(|(header, (fast, exact))| {
+                    let (depth, difference) = fast
+                        .iter()
+                        .zip(exact.iter())
+                        .map(|(fast, exact)| (fast - exact).abs())
+                        .enumerate()
+                        .fold((0, 0.0), |(best_depth, best), (depth, difference)| {
+                            if difference > best {
+                                (depth, difference)
+                            } else {
+                                (best_depth, best)
+                            }
+                        });
+                    result_string.push_str(&format!("{},{:e},{}\n", header, difference, depth));
+                });
+
+            output_default_stdout(output, result_string);
+        }
+
+        Commands::Optimise {
+            puzzle,
+            method,
+            beam_width,
+            iterations,
+            seed,
+            connected,
+            order_output,
+            output,
+        } => {
+            let puzzle_structure: PuzzleStructure = puzzle.structure();
+            let puzzle_combinations: FastCombinations = FastCombinations::new(&puzzle_structure);
+
+            let mut optimiser = OrderOptimiser::new(&puzzle_structure, &puzzle_combinations);
+            optimiser.connected = *connected;
+            let optimised = optimiser.optimise(&match method {
+                OptimiseMethod::Greedy => OptimiserMethod::Greedy,
+                OptimiseMethod::Beam => OptimiserMethod::Beam(*beam_width),
+                OptimiseMethod::Annealing => OptimiserMethod::Annealing {
+                    iterations: *iterations,
+                    seed: *seed,
+                },
+            });
+            eprintln!("{:?}: log10 total nodes {}", method, optimised.log10_total);
+
+            if let Some(path) = order_output {
+                std::fs::write(path, order_text(&optimised.search_order, path))
+                    .expect("Unable to write to file");
+                eprintln!("Order written to file: {}", path);
+            }
+
+            output_default_stdout(
+                output,
+                profile_csv(&[format!("{:?}", method)], &[optimised.search_nodes.nodes]),
+            );
+        }
+
+        Commands::OptimalOrder {
+            puzzle,
+            objective,
+            connected,
+            max_states,
+            order_output,
+            output,
+        } => {
+            let puzzle_structure: PuzzleStructure = puzzle.structure();
+            let puzzle_combinations: FastCombinations = FastCombinations::new(&puzzle_structure);
+
+            let mut optimiser = ExactOptimiser::new(&puzzle_structure, &puzzle_combinations);
+            optimiser.connected = *connected;
+            optimiser.max_states = *max_states;
+            let optimal = optimiser
+                .optimise(match objective {
+                    ObjectiveType::Total => Objective::Total,
+                    ObjectiveType::Peak => Objective::Peak,
+                })
+                .unwrap_or_else(|error| exit_with_error(&error));
+            let optimal_peak = optimal.search_nodes.log10_peak();
+            eprintln!(
+                "Optimal: log10 total nodes {}, log10 peak nodes {}",
+                optimal.log10_total, optimal_peak
+            );
+
+            if let Some(path) = order_output {
+                std::fs::write(path, order_text(&optimal.search_order, path))
+                    .expect("Unable to write to file");
+                eprintln!("Order written to file: {}", path);
+            }
+
+            let mut headers: Vec<String> = vec!["Optimal".to_string()];
+            let mut columns: Vec<Vec<f64>> = vec![optimal.search_nodes.nodes];
+            registry.strategies().iter().for_each(|strategy| {
+                let search_order = SearchOrder::from_strategy(&puzzle_structure, strategy.as_ref())
+                    .unwrap_or_else(|error| exit_with_error(&error));
+                let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+                let search_nodes = SearchNodes::new(&puzzle_combinations, &search_progress);
+                eprintln!(
+                    "{}: log10 total nodes {} (+{:.4}), log10 peak nodes {} (+{:.4})",
+                    strategy.title(),
+                    search_nodes.log10_total(),
+                    search_nodes.log10_total() - optimal.log10_total,
+                    search_nodes.log10_peak(),
+                    search_nodes.log10_peak() - optimal_peak
+                );
+                headers.push(strategy.title());
+                columns.push(search_nodes.nodes);
+            });
+
+            output_default_stdout(output, profile_csv(&headers, &columns));
+        }
+
+        Commands::Solve {
+            pieces,
+            shape,
+            searches,
+            max_solutions,
+            max_nodes,
+            solution,
+            output,
+        } => {
+            let puzzle_instance = load_instance(pieces);
+            let puzzle_structure =
+                instance_structure(&puzzle_instance, shape.as_deref().map(load_shape));
+
+            let strategies = strategies(registry, searches);
+            let mut first_solution: Option<PuzzleInstance> = None;
+            let columns: Vec<Vec<f64>> = strategies
+                .iter()
+                .map(|strategy| {
+                    let search_order =
+                        SearchOrder::from_strategy(&puzzle_structure, strategy.as_ref())
+                            .unwrap_or_else(|error| exit_with_error(&error));
+                    let mut solver =
+                        BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+                    let result = solver.solve(*max_solutions, *max_nodes);
+                    eprintln!(
+                        "{}: {} solutions, {} nodes{}",
+                        strategy.title(),
+                        result.solutions.len(),
+                        result.nodes.iter().sum::<u64>(),
+                        if result.complete {
+                            ""
+                        } else {
+                            " (stopped early)"
+                        }
+                    );
+                    if first_solution.is_none() && !result.solutions.is_empty() {
+                        first_solution = Some(result.solved_instance(&puzzle_instance, 0));
+                    }
+                    result.log10_nodes()
+                })
+                .collect();
+
+            if let Some(path) = solution {
+                match first_solution {
+                    Some(solved) => {
+                        solved
+                            .write(path)
+                            .unwrap_or_else(|error| exit_with_error(&error));
+                        eprintln!("Solution written to file: {}", path);
+                    }
+                    None => eprintln!("No solution found to write"),
+                }
+            }
+
+            let headers: Vec<String> = strategies.iter().map(|strategy| strategy.title()).collect();
+            output_default_stdout(output, profile_csv(&headers, &columns));
+        }
+
+        Commands::Estimate {
+            pieces,
+            shape,
+            searches,
+            probes,
+            seed,
+            importance,
+            output,
+        } => {
+            if *probes == 0 {
+                exit_with_error("There must be at least one probe");
+            }
+            let puzzle_instance = load_instance(pieces);
+            let puzzle_structure =
+                instance_structure(&puzzle_instance, shape.as_deref().map(load_shape));
+
+            let mut headers: Vec<String> = vec![];
+            let mut columns: Vec<Vec<f64>> = vec![];
+            strategies(registry, searches).iter().for_each(|strategy| {
+                let search_order = SearchOrder::from_strategy(&puzzle_structure, strategy.as_ref())
+                    .unwrap_or_else(|error| exit_with_error(&error));
+                let mut solver =
+                    BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+                let estimate = solver.estimate(*probes, *seed, *importance);
+                let (lower, upper) = estimate.log10_bounds(CONFIDENCE_95);
+                let name = strategy.title();
+                headers.extend([
+                    name.clone(),
+                    format!("{} Lower", name),
+                    format!("{} Upper", name),
+                ]);
+                columns.extend([estimate.log10_nodes(), lower, upper]);
+            });
+
+            output_default_stdout(output, profile_csv(&headers, &columns));
+        }
+
+        Commands::Generate {
+            x,
+            y,
+            border,
+            middle,
+            border_joins,
+            middle_joins,
+            seed,
+            solution,
+            output,
+        } => {
+            let puzzle_structure = PuzzleArgs {
+                x: Some(*x),
+                y: Some(*y),
+                border: Some(*border),
+                middle: Some(*middle),
+                border_joins: border_joins.clone(),
+                middle_joins: middle_joins.clone(),
+                pieces: None,
+                shape: None,
+                topology: TopologyType::Plane,
+                lattice: LatticeType::Square,
+                orientations: OrientationType::Rotating,
+                hint: vec![],
+            }
+            .structure();
+            let generated = GeneratedPuzzle::new(&puzzle_structure, *seed)
+                .unwrap_or_else(|error| exit_with_error(&error));
+
+            if let Some(path) = solution {
+                generated
+                    .solution
+                    .write(path)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+                eprintln!("Solution written to file: {}", path);
+            }
+
+            output_default_stdout(output, generated.puzzle_instance.to_text());
+        }
+
+        Commands::CubeProfile {
+            x,
+            y,
+            z,
+            outer,
+            inner,
+            searches,
+            summary,
+            output,
+        } => {
+            let cube_structure = CubeStructure::new(*x, *y, *z, *outer, *inner);
+            let cube_combinations = CubeCombinations::new(&cube_structure);
+            let headers: Vec<String> = searches
+                .iter()
+                .map(|search| format!("{:?}", search))
+                .collect();
+            let search_nodes: Vec<SearchNodes> = searches
+                .iter()
+                .map(|search_type| {
+                    let cube_order =
+                        CubeOrder::new(*x, *y, *z, CubeSearchOption::from(*search_type));
+                    let cube_progress = CubeProgress::new(&cube_structure, &cube_order);
+                    SearchNodes::new(&cube_combinations, &cube_progress)
+                })
+                .collect();
+
+            if let Some(summary) = summary {
+                let summaries: Vec<SearchSummary> =
+                    search_nodes.iter().map(SearchSummary::new).collect();
+                output_default_stdout(
+                    output,
+                    match summary {
+                        SummaryFormat::Table => summary_table(&headers, &summaries),
+                        SummaryFormat::Json => summary_json(&headers, &summaries),
+                    },
+                );
+                return;
+            }
+
+            let columns: Vec<Vec<f64>> = search_nodes
+                .into_iter()
+                .map(|search_node| search_node.nodes)
+                .collect();
+            output_default_stdout(output, profile_csv(&headers, &columns));
+        }
+
+        Commands::Sweep {
+            x,
+            y,
+            border,
+            middle,
+            skews,
+            searches,
+            threads,
+            format,
+            output,
+        } => {
+            if *x.start() <= 1 || *y.start() <= 1 {
+                exit_with_error("Width and height must be greater than 1");
+            }
+            if skews.iter().any(|skew| *skew <= 0.0) {
+                exit_with_error("Skews must be greater than 0");
+            }
+            let strategies = strategies(registry, searches);
+            let sweep = ParameterSweep::new(
+                x.clone(),
+                y.clone(),
+                border.clone(),
+                middle.clone(),
+                skews,
+                &strategies,
+            );
+            let threads = threads.unwrap_or_else(|| {
+                std::thread::available_parallelism().map_or(1, |threads| threads.get())
+            });
+            if threads == 0 {
+                exit_with_error("There must be at least one thread");
+            }
+            let results = sweep
+                .run(threads, FastCombinations::new)
+                .unwrap_or_else(|error| exit_with_error(&error));
+            let headers: Vec<String> = strategies.iter().map(|strategy| strategy.title()).collect();
+
+            output_default_stdout(
+                output,
+                match format {
+                    SweepFormat::Csv => sweep_csv(&headers, &results),
+                    SweepFormat::Json => sweep_json(&headers, &results),
+                },
+            );
+        }
+
+        Commands::Critical {
+            x,
+            y,
+            border,
+            skews,
+            target,
+            searches,
+            output,
+        } => {
+            if *x <= 1 || *y <= 1 {
+                exit_with_error("Width and height must be greater than 1");
+            }
+            if skews.iter().any(|skew| *skew <= 0.0) {
+                exit_with_error("Skews must be greater than 0");
+            }
+            if *target <= 0.0 {
+                exit_with_error("Target must be greater than 0");
+            }
+            let strategies = strategies(registry, searches);
+            let points = critical_points(
+                *x,
+                *y,
+                *border,
+                skews,
+                target.log10(),
+                &strategies,
+                FastCombinations::new,
+            )
+            .unwrap_or_else(|error| exit_with_error(&error));
+
+            let mut result_string =
+                String::from("Skew,Middle,Middle Estimate,Middle Joins,Log10 Solutions");
+            strategies.iter().for_each(|strategy| {
+                result_string.push_str(&format!(",{} Log10 Total", strategy.title()))
+            });
+            result_string.push('\n');
+            points.iter().for_each(|point| {
+                result_string.push_str(&format!(
+                    "{},{},{},{},{}",
+                    point.skew,
+                    point.middle_types,
+                    point.middle_estimate,
+                    point
+                        .middle_join_counts
+                        .iter()
+                        .map(|count| count.to_string())
+                        .collect::<Vec<String>>()
+                        .join(";"),
+                    point.log10_solutions
+                ));
+                point.summaries.iter().for_each(|summary| {
+                    result_string.push_str(&format!(",{}", summary.log10_total))
+                });
+                result_string.push('\n');
+            });
+            skews
+                .iter()
+                .filter(|skew| !points.iter().any(|point| point.skew == **skew))
+                .for_each(|skew| eprintln!("Skew {} never reaches the target", skew));
+
+            output_default_stdout(output, result_string);
+        }
+
+        Commands::Inspect {
+            pieces,
+            shape,
+            output,
+        } => {
+            let puzzle_instance = load_instance(pieces);
+            let puzzle_structure =
+                instance_structure(&puzzle_instance, shape.as_deref().map(load_shape));
+            let hints: Vec<Location> = puzzle_instance
+                .pieces
+                .iter()
+                .filter_map(|piece| piece.fixed)
+                .map(|placement| Location {
+                    x: placement.x,
+                    y: placement.y,
+                })
+                .collect();
+
+            let mut result_string = String::new();
+            result_string.push_str(&format!("Width: {}\n", puzzle_structure.width));
+            result_string.push_str(&format!("Height: {}\n", puzzle_structure.height));
+            result_string.push_str(&format!(
+                "Border types: {}\n",
+                puzzle_structure.border_types
+            ));
+            result_string.push_str(&format!(
+                "Middle types: {}\n",
+                puzzle_structure.middle_types
+            ));
+            result_string.push_str(&format!(
+                "Border join counts: {}\n",
+                join_list(&puzzle_structure.border_join_counts)
+            ));
+            result_string.push_str(&format!(
+                "Middle join counts: {}\n",
+                join_list(&puzzle_structure.middle_join_counts)
+            ));
+            result_string.push_str(&format!("Hints: {}\n", hints.len()));
+            result_string.push_str(&format!(
+                "Arguments: {}\n",
+                profile_arguments(&puzzle_structure, shape.as_deref(), &hints)
+            ));
+
+            output_default_stdout(output, result_string);
+        }
+    }
+}
+
+/// Arguments that give `profile` the same puzzle: the shape file if there is
+/// one, else the size, then the edge types, join counts and one `--hint` for
+/// each hinted cell.
+fn profile_arguments(
+    puzzle_structure: &PuzzleStructure,
+    shape: Option<&str>,
+    hints: &[Location],
+) -> String {
+    let mut arguments = match shape {
+        Some(path) => format!("--shape {}", path),
+        None => format!(
+            "-x {} -y {}",
+            puzzle_structure.width, puzzle_structure.height
+        ),
+    };
+    arguments.push_str(&format!(
+        " -b {} -m {} --border-joins {} --middle-joins {}",
+        puzzle_structure.border_types,
+        puzzle_structure.middle_types,
+        join_list(&puzzle_structure.border_join_counts),
+        join_list(&puzzle_structure.middle_join_counts)
+    ));
+    hints
+        .iter()
+        .for_each(|hint| arguments.push_str(&format!(" --hint {},{}", hint.x, hint.y)));
+    arguments
+}
+
+/// Headers for the join probabilities and the combinations of each piece
+/// class, numbering the classes of a location type that holds several.
+fn probability_headers(puzzle_structure: &PuzzleStructure) -> Vec<String> {
+    let classes = &puzzle_structure.piece_classes;
+    let mut headers = vec![
+        "Middle Probability".to_string(),
+        "Border Probability".to_string(),
+    ];
+    classes.iter().enumerate().for_each(|(index, class)| {
+        let name = match class.location_type {
+            LocationType::Corner => "Corner",
+            LocationType::Edge => "Edge",
+            LocationType::Interior => "Interior",
+        };
+        let same_type = |other: &PieceClass| other.location_type == class.location_type;
+        if classes.iter().filter(|other| same_type(other)).count() == 1 {
+            headers.push(format!("{} Combinations", name));
+        } else {
+            let number = classes[..=index]
+                .iter()
+                .filter(|other| same_type(other))
+                .count();
+            headers.push(format!("{} {} Combinations", name, number));
+        }
+    });
+    headers
+}
+
+fn probability_columns(
+    log10_middle: &[f64],
+    log10_border: &[f64],
+    log10_pieces: &[Vec<f64>],
+) -> Vec<Vec<f64>> {
+    [log10_middle.to_vec(), log10_border.to_vec()]
+        .into_iter()
+        .chain(log10_pieces.iter().cloned())
+        .collect()
+}
+
+/// Lays out the log10 join probabilities and piece combinations against the
+/// number of joins or pieces used, leaving a cell empty past the end of a list.
+fn probability_csv(headers: &[String], columns: &[Vec<f64>]) -> String {
+    let mut result_string = String::new();
+    result_string.push_str("Depth");
+    for header in headers {
+        result_string.push_str(&format!(",{}", header));
+    }
+    result_string.push('\n');
+
+    let max_depth = columns.iter().map(|column| column.len()).max().unwrap();
+    for depth in 0..max_depth {
+        result_string.push_str(&format!("{}", depth));
+        for column in columns {
+            match column.get(depth) {
+                Some(value) => result_string.push_str(&format!(",{}", value)),
+                None => result_string.push(','),
+            }
+        }
+        result_string.push('\n');
+    }
+    result_string
+}
+
+/// Looks up the named search orders, exiting on an unknown name.
+fn strategies(registry: &SearchRegistry, searches: &[String]) -> Vec<Arc<dyn SearchOrderStrategy>> {
+    registry
+        .resolve(searches)
+        .unwrap_or_else(|error| exit_with_error(&error))
+}
+
+/// Builds the named orders followed by the orders read from files, with a
+/// column name for each.
+fn search_orders(
+    puzzle_structure: &PuzzleStructure,
+    strategies: &[Arc<dyn SearchOrderStrategy>],
+    order_file: &[String],
+) -> (Vec<String>, Vec<SearchOrder>) {
+    let mut headers: Vec<String> = strategies.iter().map(|strategy| strategy.title()).collect();
+    headers.extend(order_file.iter().map(|path| order_label(path)));
+
+    let mut search_orders: Vec<SearchOrder> = strategies
+        .iter()
+        .map(|strategy| {
+            SearchOrder::from_strategy(puzzle_structure, strategy.as_ref())
+                .unwrap_or_else(|error| exit_with_error(&error))
+        })
+        .collect();
+    search_orders.extend(
+        order_file
+            .iter()
+            .map(|path| load_order(path, puzzle_structure)),
+    );
+    (headers, search_orders)
+}
+
+/// Lays out one column of log10 values per search with a row for each depth,
+/// leaving a cell empty where a column is shorter than the others or has no
+/// finite value, such as the log10 of a depth no probe reached.
+fn profile_csv(headers: &[String], columns: &[Vec<f64>]) -> String {
+    let mut result_string = String::new();
+    result_string.push_str("Depth");
+    for header in headers {
+        result_string.push_str(&format!(",{}", header));
+    }
+    result_string.push('\n');
+
+    let depths = columns.iter().map(|column| column.len()).max().unwrap_or(0);
+    for depth in 0..depths {
+        result_string.push_str(&format!("{}", depth));
+        for column in columns {
+            match column.get(depth) {
+                Some(value) if value.is_finite() => result_string.push_str(&format!(",{}", value)),
+                _ => result_string.push(','),
+            }
+        }
+        result_string.push('\n');
+    }
+    result_string
+}
+
+/// Ranks the orders, then lists the log10 branching factor at each depth.
+fn summary_table(headers: &[String], summaries: &[SearchSummary]) -> String {
+    let ranking = SearchSummary::rank(summaries);
+    let name_width = headers
+        .iter()
+        .map(|header| header.len())
+        .chain(["Order".len()])
+        .max()
+        .unwrap();
+
+    let mut result_string = String::new();
+    result_string.push_str(&format!(
+        "{:<4}  {:<name_width$}  {:>11}  {:>10}  {:>10}  {:>15}\n",
+        "Rank", "Order", "Log10 total", "Peak depth", "Log10 peak", "Log10 solutions"
+    ));
+    ranking.iter().enumerate().for_each(|(rank, index)| {
+        let summary = &summaries[*index];
+        result_string.push_str(&format!(
+            "{:<4}  {:<name_width$}  {:>11.4}  {:>10}  {:>10.4}  {:>15.4}\n",
+            rank + 1,
+            headers[*index],
+            summary.log10_total,
+            summary.peak_depth,
+            summary.log10_peak,
+            summary.log10_solutions
+        ));
+    });
+
+    result_string.push_str("\nLog10 branching factor\n");
+    result_string.push_str("Depth");
+    ranking.iter().for_each(|index| {
+        let width = headers[*index].len().max(8);
+        result_string.push_str(&format!("  {:>width$}", headers[*index]));
+    });
+    result_string.push('\n');
+    let depths = summaries
+        .iter()
+        .map(|summary| summary.log10_branching.len())
+        .max()
+        .unwrap_or(0);
+    (0..depths).for_each(|depth| {
+        result_string.push_str(&format!("{:<5}", depth + 1));
+        ranking.iter().for_each(|index| {
+            let width = headers[*index].len().max(8);
+            match summaries[*index].log10_branching.get(depth) {
+                Some(branching) => result_string.push_str(&format!("  {:>width$.4}", branching)),
+                None => result_string.push_str(&format!("  {:>width$}", "")),
+            }
+        });
+        result_string.push('\n');
+    });
+    result_string
+}
+
+#[derive(Serialize)]
+struct SummaryRow<'a> {
+    rank: usize,
+    order: &'a str,
+    log10_total: f64,
+    peak_depth: usize,
+    log10_peak: f64,
+    log10_solutions: f64,
+    /// Entry `i` is the change from depth `i` to depth `i + 1`.
+    log10_branching: &'a [f64],
+}
+
+fn summary_json(headers: &[String], summaries: &[SearchSummary]) -> String {
+    let rows: Vec<SummaryRow> = SearchSummary::rank(summaries)
+        .iter()
+        .enumerate()
+        .map(|(rank, index)| {
+            let summary = &summaries[*index];
+            SummaryRow {
+                rank: rank + 1,
+                order: &headers[*index],
+                log10_total: summary.log10_total,
+                peak_depth: summary.peak_depth,
+                log10_peak: summary.log10_peak,
+                log10_solutions: summary.log10_solutions,
+                log10_branching: &summary.log10_branching,
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).expect("Unable to write summary as JSON")
+}
+
+/// One row per point and search order.
+fn sweep_csv(headers: &[String], results: &[SweepResult]) -> String {
+    let mut result_string = String::from(
+        "Width,Height,Border,Middle,Skew,Order,Log10 Total,Peak Depth,Log10 Peak,Log10 Solutions\n",
+    );
+    results.iter().for_each(|result| {
+        let point = &result.point;
+        headers
+            .iter()
+            .zip(result.summaries.iter())
+            .for_each(|(header, summary)| {
+                result_string.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{},{}\n",
+                    point.width,
+                    point.height,
+                    point.border_types,
+                    point.middle_types,
+                    point.skew,
+                    header,
+                    summary.log10_total,
+                    summary.peak_depth,
+                    summary.log10_peak,
+                    summary.log10_solutions
+                ));
+            });
+    });
+    result_string
+}
+
+#[derive(Serialize)]
+struct SweepRow<'a> {
+    width: usize,
+    height: usize,
+    border: usize,
+    middle: usize,
+    skew: f64,
+    order: &'a str,
+    log10_total: f64,
+    peak_depth: usize,
+    log10_peak: f64,
+    log10_solutions: f64,
+}
+
+fn sweep_json(headers: &[String], results: &[SweepResult]) -> String {
+    let rows: Vec<SweepRow> = results
+        .iter()
+        .flat_map(|result| {
+            let point = &result.point;
+            headers
+                .iter()
+                .zip(result.summaries.iter())
+                .map(move |(header, summary)| SweepRow {
+                    width: point.width,
+                    height: point.height,
+                    border: point.border_types,
+                    middle: point.middle_types,
+                    skew: point.skew,
+                    order: header,
+                    log10_total: summary.log10_total,
+                    peak_depth: summary.peak_depth,
+                    log10_peak: summary.log10_peak,
+                    log10_solutions: summary.log10_solutions,
+                })
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).expect("Unable to write sweep as JSON")
+}
+
+impl PuzzleArgs {
+    /// Builds the puzzle structure from the size or shape and edge type
+    /// arguments, or from a piece file, then applies the lattice, topology,
+    /// orientations and any join counts and hints given on the command line.
+    fn structure(&self) -> PuzzleStructure {
+        let mut puzzle_structure: PuzzleStructure = match (&self.pieces, &self.shape) {
+            (Some(path), shape) => {
+                instance_structure(&load_instance(path), shape.as_deref().map(load_shape))
+            }
+            (None, Some(path)) => {
+                let [border, middle] = [self.border, self.middle]
+                    .map(|parameter| parameter.expect("Missing puzzle parameter"));
+                PuzzleStructure::from_shape(&load_shape(path), border, middle)
+            }
+            (None, None) => {
+                let [x, y, border, middle] = [self.x, self.y, self.border, self.middle]
+                    .map(|parameter| parameter.expect("Missing puzzle parameter"));
+                PuzzleStructure::new(x, y, border, middle)
+            }
+        };
+
+        puzzle_structure = match self.lattice {
+            LatticeType::Square => Ok(puzzle_structure),
+            LatticeType::Hexagonal => puzzle_structure.with_lattice(Lattice::Hexagonal),
+            LatticeType::Triangular => puzzle_structure.with_lattice(Lattice::Triangular),
+        }
+        .and_then(|puzzle_structure| match self.topology {
+            TopologyType::Plane => Ok(puzzle_structure),
+            TopologyType::Cylinder => puzzle_structure.with_topology(Topology::Cylinder),
+            TopologyType::Torus => puzzle_structure.with_topology(Topology::Torus),
+        })
+        .unwrap_or_else(|error| exit_with_error(&error));
+
+        let lattice = puzzle_structure.lattice;
+        puzzle_structure.orientations = match self.orientations {
+            OrientationType::Rotating => Orientations::rotating(lattice),
+            OrientationType::Fixed => Orientations::fixed(),
+            OrientationType::Flippable => Orientations::flippable(lattice),
+            OrientationType::Rectangular => Orientations::rectangular(),
+        };
+
+        if let Some(middles) = &self.middle_joins {
+            puzzle_structure.middle_join_counts = middles.clone();
+        }
+        if let Some(borders) = &self.border_joins {
+            puzzle_structure.border_join_counts = borders.clone();
+        }
+        if !self.hint.is_empty() {
+            let mut hints: Vec<Location> = puzzle_structure
+                .grid
+                .iter()
+                .flatten()
+                .filter(|location| location.hint)
+                .map(|location| Location {
+                    x: location.x,
+                    y: location.y,
+                })
+                .collect();
+            hints.extend(self.hint.iter().cloned());
+            puzzle_structure
+                .set_hints(&hints)
+                .unwrap_or_else(|error| exit_with_error(&error));
+        }
+        puzzle_structure
+    }
+}
+
+/// Reads a piece file, exiting with the reason if it cannot be used.
+fn load_instance(path: &str) -> PuzzleInstance {
+    PuzzleInstance::read(path).unwrap_or_else(|error| exit_with_error(&error))
+}
+
+/// Structure of the board a piece file fills, which is a full rectangle
+/// unless a shape is given.
+fn instance_structure(
+    puzzle_instance: &PuzzleInstance,
+    shape: Option<BoardShape>,
+) -> PuzzleStructure {
+    match shape {
+        Some(shape) => puzzle_instance.structure_for_shape(&shape),
+        None => puzzle_instance.structure(),
+    }
+    .unwrap_or_else(|error| exit_with_error(&error))
+}
+
+/// Reads a search order from a JSON file holding a list of `[x, y]` pairs or
+/// `{"x": .., "y": ..}` objects, or otherwise from CSV lines of `x,y`.
+fn load_order(path: &str, puzzle_structure: &PuzzleStructure) -> SearchOrder {
+    SearchOrder::from_structure_locations(puzzle_structure, load_locations(path))
+        .unwrap_or_else(|error| exit_with_error(&format!("{}: {}", path, error)))
+}
+
+/// Reads a board shape from ASCII art, CSV lines of `x,y` or a JSON list of
+/// cells in the same form as an order file.
+fn load_shape(path: &str) -> BoardShape {
+    let shape = if path.to_lowercase().ends_with(".json") {
+        BoardShape::from_locations(&load_locations(path))
+            .map_err(|error| format!("{}: {}", path, error))
+    } else {
+        BoardShape::read(path)
+    };
+    shape.unwrap_or_else(|error| exit_with_error(&error))
+}
+
+fn load_locations(path: &str) -> Vec<Location> {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|error| exit_with_error(&format!("Unable to read {}: {}", path, error)));
+
+    let locations = if path.to_lowercase().ends_with(".json") {
+        parse_json_locations(&text)
+    } else {
+        parse_locations(&text)
+    };
+    locations.unwrap_or_else(|error| exit_with_error(&format!("{}: {}", path, error)))
+}
+
+/// An order in the format an order file's name calls for.
+fn order_text(search_order: &SearchOrder, path: &str) -> String {
+    if path.to_lowercase().ends_with(".json") {
+        search_order.to_json()
+    } else {
+        search_order.to_csv()
+    }
+}
+
+/// Column name for an order file, which is its file name without extension.
+fn order_label(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn parse_location(text: &str) -> Result<Location, String> {
+    let values: Vec<usize> = text
+        .split(',')
+        .map(|value| value.trim().parse::<usize>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("{} is not a pair of integers", text))?;
+    match values[..] {
+        [x, y] => Ok(Location { x, y }),
+        _ => Err(format!("{} should be given as X,Y", text)),
+    }
+}
+
+/// Parses a single value or an inclusive MIN-MAX range.
+fn parse_range(text: &str) -> Result<RangeInclusive<usize>, String> {
+    let values: Vec<usize> = text
+        .split('-')
+        .map(|value| value.trim().parse::<usize>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("{} is not an integer or range of integers", text))?;
+    match values[..] {
+        [value] => Ok(value..=value),
+        [min, max] if min <= max => Ok(min..=max),
+        [_, _] => Err(format!(
+            "{} should run from the smaller value to the larger",
+            text
+        )),
+        _ => Err(format!("{} should be given as MIN-MAX", text)),
+    }
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    std::process::exit(1);
+}
+
+fn join_list(values: &[usize]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Outputs the result to a file or stdout
+/// If the output is "-", it writes to stdout.
+/// Otherwise, it writes to the specified file.
+/// If the file already exists, it will be overwritten.
+/// If the file cannot be written, it will panic.
+fn output_default_stdout(output: &str, string: String) {
+    if output != "-" {
+        std::fs::write(output, string).expect("Unable to write to file");
+        eprintln!("Data written to file: {}", output);
+    } else {
+        println!("{}", string);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        order_label, parse_location, parse_range, profile_csv, summary_json, summary_table,
+        sweep_csv,
+    };
+    use edgelib::model::{Location, SearchSummary};
+    use edgelib::sweep::{SweepPoint, SweepResult};
+
+    fn summary(log10_total: f64, log10_peak: f64, log10_branching: Vec<f64>) -> SearchSummary {
+        SearchSummary {
+            log10_total,
+            peak_depth: 2,
+            log10_peak,
+            log10_solutions: -1.0,
+            log10_branching,
+        }
+    }
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_profile_csv() {
+        let columns = vec![vec![0.0, 1.5], vec![0.0, f64::NEG_INFINITY, 2.0]];
+        assert_eq!(
+            profile_csv(&headers(&["A", "B"]), &columns),
+            "Depth,A,B\n0,0,0\n1,1.5,\n2,,2\n"
+        );
+    }
+
+    #[test]
+    fn test_summary_table() {
+        let summaries = [
+            summary(5.0, 3.0, vec![1.0, 0.5, -1.0]),
+            summary(4.0, 3.5, vec![1.0]),
+            summary(4.0, 3.0, vec![1.0, 0.5]),
+        ];
+        let table = summary_table(
+            &headers(&["Slow", "Peaky", "A long order name"]),
+            &summaries,
+        );
+        let (ranking, branching) = table.split_once("\n\n").unwrap();
+
+        // Ties on the total go to the lower peak.
+        let rows: Vec<&str> = ranking.lines().collect();
+        assert!(rows[1].starts_with("1     A long order name"));
+        assert!(rows[2].starts_with("2     Peaky"));
+        assert!(rows[3].starts_with("3     Slow"));
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+
+        // Shorter columns are padded so the rows stay aligned.
+        let lines: Vec<&str> = branching.lines().collect();
+        assert_eq!(lines[0], "Log10 branching factor");
+        assert!(lines[1].ends_with("A long order name     Peaky      Slow"));
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2..].iter().all(|line| line.len() == lines[1].len()));
+        assert!(lines[4].starts_with("3    "));
+        assert!(lines[4].ends_with("-1.0000"));
+    }
+
+    #[test]
+    fn test_summary_json() {
+        let summaries = [summary(5.0, 3.0, vec![1.0]), summary(4.0, 3.0, vec![2.0])];
+        let json: serde_json::Value =
+            serde_json::from_str(&summary_json(&headers(&["Slow", "Fast"]), &summaries)).unwrap();
+        assert_eq!(json[0]["rank"], 1);
+        assert_eq!(json[0]["order"], "Fast");
+        assert_eq!(json[0]["log10_branching"][0], 2.0);
+        assert_eq!(json[1]["order"], "Slow");
+    }
+
+    #[test]
+    fn test_sweep_csv() {
+        let results = [SweepResult {
+            point: SweepPoint {
+                width: 4,
+                height: 3,
+                border_types: 1,
+                middle_types: 2,
+                skew: 0.5,
+            },
+            summaries: vec![summary(5.0, 3.0, vec![]), summary(4.0, 3.0, vec![])],
+        }];
+        let lines: Vec<String> = sweep_csv(&headers(&["Slow", "Fast"]), &results)
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "4,3,1,2,0.5,Slow,5,2,3,-1");
+        assert_eq!(lines[2], "4,3,1,2,0.5,Fast,4,2,3,-1");
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("3"), Ok(3..=3));
+        assert_eq!(parse_range("2-5"), Ok(2..=5));
+        assert_eq!(parse_range(" 2 - 5 "), Ok(2..=5));
+        assert!(parse_range("5-2").is_err());
+        assert!(parse_range("1-2-3").is_err());
+        assert!(parse_range("a-3").is_err());
+        assert!(parse_range("").is_err());
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(parse_location("1,2"), Ok(Location { x: 1, y: 2 }));
+        assert_eq!(parse_location(" 3 , 0 "), Ok(Location { x: 3, y: 0 }));
+        assert!(parse_location("1").is_err());
+        assert!(parse_location("1,2,3").is_err());
+        assert!(parse_location("x,1").is_err());
+        assert!(parse_location("-1,1").is_err());
+    }
+
+    #[test]
+    fn test_order_label() {
+        assert_eq!(order_label("orders/best.json"), "best");
+        assert_eq!(order_label("spiral.csv"), "spiral");
+        assert_eq!(order_label("plain"), "plain");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::model::{
+    NodeCombinatorics, generate_combinations, generate_factorials, generate_permutions,
+    middle_joins,
+};
+
+use super::{CubeLocationType, CubeProgress, CubeStructure, CubeUsed};
+
+/// Combinations and join probabilities for cubes in a box. Any cube that
+/// shows a face to the outside can still turn while keeping it there, so the
+/// faces of both outer and inner joins are drawn from one pool per colour,
+/// as middle joins are on a flat board.
+pub struct CubeCombinations {
+    pub log10_outer: Vec<f64>,
+    pub log10_inner: Vec<f64>,
+    pub log10_vertex: Vec<f64>,
+    pub log10_edge: Vec<f64>,
+    pub log10_face: Vec<f64>,
+    pub log10_interior: Vec<f64>,
+}
+
+impl CubeCombinations {
+    pub fn new(cube_structure: &CubeStructure) -> Self {
+        let size1: usize = cube_structure.outer_join_counts.iter().sum::<usize>() * 2;
+        let size2: usize = cube_structure.inner_join_counts.iter().sum::<usize>() * 2;
+        let max_size = size1.max(size2).max(cube_structure.cells());
+        let f: Vec<f64> = generate_factorials(max_size);
+        let p: Vec<Vec<f64>> = generate_permutions(&f);
+        let c: Vec<Vec<f64>> = generate_combinations(&f);
+
+        let pieces = |count: usize, location_type: CubeLocationType| -> Vec<f64> {
+            let orientations = (location_type.orientations() as f64).log10();
+            p[count]
+                .iter()
+                .enumerate()
+                .map(|(index, combinations)| combinations + orientations * (index as f64))
+                .collect()
+        };
+
+        CubeCombinations {
+            log10_outer: middle_joins(&cube_structure.outer_join_counts, &p, &c),
+            log10_inner: middle_joins(&cube_structure.inner_join_counts, &p, &c),
+            log10_vertex: pieces(cube_structure.vertices, CubeLocationType::Vertex),
+            log10_edge: pieces(cube_structure.edges, CubeLocationType::Edge),
+            log10_face: pieces(cube_structure.faces, CubeLocationType::Face),
+            log10_interior: pieces(cube_structure.interiors, CubeLocationType::Interior),
+        }
+    }
+}
+
+impl NodeCombinatorics for CubeCombinations {
+    type Progress = CubeProgress;
+
+    /// log10 of the expected number of partial solutions with these cubes and joins.
+    fn log10_nodes(&self, used: &CubeUsed) -> f64 {
+        self.log10_outer[used.outers]
+            + self.log10_inner[used.inners]
+            + self.log10_vertex[used.vertices]
+            + self.log10_edge[used.edges]
+            + self.log10_face[used.faces]
+            + self.log10_interior[used.interiors]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CubeCombinations;
+    use crate::cube::{CubeOrder, CubeProgress, CubeSearchOption, CubeStructure};
+    use crate::model::SearchNodes;
+
+    #[test]
+    fn test_cube_nodes() {
+        let cube_structure = CubeStructure::new(2, 2, 2, 1, 1);
+        let cube_combinations = CubeCombinations::new(&cube_structure);
+        let cube_order = CubeOrder::new(2, 2, 2, CubeSearchOption::ScanLayers);
+        let cube_progress = CubeProgress::new(&cube_structure, &cube_order);
+        let search_nodes = SearchNodes::new(&cube_combinations, &cube_progress);
+
+        // With one colour every join matches, leaving 8! placements with 3
+        // turns for each vertex cube.
+        assert_eq!(search_nodes.nodes.len(), 9);
+        let log10_solutions = (1..=8).map(|n| (n as f64).log10()).sum::<f64>() + 8.0 * 3f64.log10();
+        assert!((search_nodes.nodes[8] - log10_solutions).abs() < 1e-9);
+        assert_eq!(cube_progress.end_point, cube_progress.progress[8]);
+    }
+}
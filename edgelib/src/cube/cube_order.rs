@@ -0,0 +1,105 @@
+pub struct CubeOrder {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub size: usize,
+    pub order: Vec<CubeLocation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CubeLocation {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeSearchOption {
+    /// One `z` layer at a time, each scanned in rows.
+    ScanLayers,
+    /// The outer shell of the box first, then each shell inside it.
+    ShellIn,
+    /// The innermost shell first, working out to the surface.
+    ShellOut,
+    /// Every cube on the surface in layer order, then the interior.
+    SurfaceFirst,
+    /// The interior in layer order, then the surface.
+    SurfaceLast,
+}
+
+impl CubeSearchOption {
+    pub fn all() -> Vec<CubeSearchOption> {
+        vec![
+            CubeSearchOption::ScanLayers,
+            CubeSearchOption::ShellIn,
+            CubeSearchOption::ShellOut,
+            CubeSearchOption::SurfaceFirst,
+            CubeSearchOption::SurfaceLast,
+        ]
+    }
+}
+
+impl CubeOrder {
+    pub fn new(width: usize, height: usize, depth: usize, search_option: CubeSearchOption) -> Self {
+        if width <= 1 || height <= 1 || depth <= 1 {
+            panic!("Width, height and depth must be greater than 1");
+        }
+
+        let size = [width, height, depth];
+        // Layers along z, rows along y and cells along x.
+        let mut order: Vec<CubeLocation> = (0..depth)
+            .flat_map(|z| {
+                (0..height).flat_map(move |y| (0..width).map(move |x| CubeLocation { x, y, z }))
+            })
+            .collect();
+        let shell = |location: &CubeLocation| {
+            [location.x, location.y, location.z]
+                .iter()
+                .zip(size)
+                .map(|(value, size)| (*value).min(size - 1 - value))
+                .min()
+                .unwrap()
+        };
+        // Sorts are stable, so each shell keeps the layer order.
+        match search_option {
+            CubeSearchOption::ScanLayers => {}
+            CubeSearchOption::ShellIn => order.sort_by_key(shell),
+            CubeSearchOption::ShellOut => {
+                order.sort_by_key(|location| std::cmp::Reverse(shell(location)))
+            }
+            CubeSearchOption::SurfaceFirst => order.sort_by_key(|location| shell(location) > 0),
+            CubeSearchOption::SurfaceLast => order.sort_by_key(|location| shell(location) == 0),
+        }
+
+        CubeOrder {
+            width,
+            height,
+            depth,
+            size: width * height * depth,
+            order,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CubeLocation, CubeOrder, CubeSearchOption};
+
+    #[test]
+    fn test_orders() {
+        CubeSearchOption::all()
+            .into_iter()
+            .for_each(|search_option| {
+                let cube_order = CubeOrder::new(3, 4, 5, search_option);
+                let mut order = cube_order.order.clone();
+                order.sort_by_key(|location| (location.x, location.y, location.z));
+                order.dedup();
+                assert_eq!(order.len(), cube_order.size);
+            });
+
+        let shell_in = CubeOrder::new(3, 3, 3, CubeSearchOption::ShellIn);
+        assert_eq!(shell_in.order[26], CubeLocation { x: 1, y: 1, z: 1 });
+        let shell_out = CubeOrder::new(3, 3, 3, CubeSearchOption::ShellOut);
+        assert_eq!(shell_out.order[0], CubeLocation { x: 1, y: 1, z: 1 });
+    }
+}
@@ -0,0 +1,90 @@
+use crate::model::Progress;
+
+use super::{CubeJoinType, CubeLocationType, CubeOrder, CubeStructure};
+
+#[derive(Clone, Debug)]
+pub struct CubeProgress {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub size: usize,
+    pub end_point: CubeUsed,
+    pub progress: Vec<CubeUsed>,
+}
+
+impl Progress for CubeProgress {
+    type Used = CubeUsed;
+
+    fn progress(&self) -> &[CubeUsed] {
+        &self.progress
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CubeUsed {
+    pub vertices: usize,
+    pub edges: usize,
+    pub faces: usize,
+    pub interiors: usize,
+    pub outers: usize,
+    pub inners: usize,
+}
+
+impl CubeProgress {
+    pub fn new(cube_structure: &CubeStructure, cube_order: &CubeOrder) -> Self {
+        if cube_order.width != cube_structure.width
+            || cube_order.height != cube_structure.height
+            || cube_order.depth != cube_structure.depth
+        {
+            panic!("Search order and Cube structure size needs to match");
+        }
+
+        let end_point = CubeUsed {
+            vertices: cube_structure.vertices,
+            edges: cube_structure.edges,
+            faces: cube_structure.faces,
+            interiors: cube_structure.interiors,
+            outers: cube_structure.outer_join_counts.iter().sum(),
+            inners: cube_structure.inner_join_counts.iter().sum(),
+        };
+
+        let mut filled: Vec<Vec<Vec<bool>>> =
+            vec![
+                vec![vec![false; cube_structure.depth]; cube_structure.height];
+                cube_structure.width
+            ];
+        let mut current_used = CubeUsed::default();
+        let mut progress: Vec<CubeUsed> = vec![current_used.clone()];
+
+        cube_order.order.iter().for_each(|location| {
+            let grid_location = &cube_structure.grid[location.x][location.y][location.z];
+            let mut next_used = current_used.clone();
+            grid_location
+                .joins
+                .iter()
+                .filter(|join| filled[join.x][join.y][join.z])
+                .for_each(|join| match join.join_type {
+                    CubeJoinType::Outer => next_used.outers += 1,
+                    CubeJoinType::Inner => next_used.inners += 1,
+                });
+            match grid_location.location_type {
+                CubeLocationType::Vertex => next_used.vertices += 1,
+                CubeLocationType::Edge => next_used.edges += 1,
+                CubeLocationType::Face => next_used.faces += 1,
+                CubeLocationType::Interior => next_used.interiors += 1,
+            }
+            filled[location.x][location.y][location.z] = true;
+            progress.push(next_used.clone());
+            current_used = next_used;
+        });
+
+        CubeProgress {
+            width: cube_structure.width,
+            height: cube_structure.height,
+            depth: cube_structure.depth,
+            size: progress.len() - 1,
+            end_point,
+            progress,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+#[derive(Debug)]
+pub struct CubeStructure {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub outer_types: usize,
+    pub inner_types: usize,
+    /// Indexed `[x][y][z]`.
+    pub grid: Vec<Vec<Vec<CubeGridLocation>>>,
+    pub vertices: usize,
+    pub edges: usize,
+    pub faces: usize,
+    pub interiors: usize,
+    pub outer_joins: usize,
+    pub inner_joins: usize,
+    pub outer_join_counts: Vec<usize>,
+    pub inner_join_counts: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CubeGridLocation {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub location_type: CubeLocationType,
+    pub joins: Vec<CubeJoin>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CubeJoin {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub join_type: CubeJoinType,
+}
+
+/// Kind of cube that fits a cell, from how many of its faces are on the
+/// outside of the box.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CubeLocationType {
+    Vertex,
+    Edge,
+    Face,
+    Interior,
+}
+
+/// Outer joins run along the outside of the box, between two cells that both
+/// show a face to the outside next to the join. Every other join is inner.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CubeJoinType {
+    Outer,
+    Inner,
+}
+
+pub const CUBE_COORDINATES: [(isize, isize, isize); 6] = [
+    (1, 0, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (-1, 0, 0),
+    (0, -1, 0),
+    (0, 0, -1),
+];
+
+impl CubeLocationType {
+    /// Of the 24 ways to turn a cube, how many keep its frame faces on the
+    /// outside of the box.
+    pub fn orientations(&self) -> usize {
+        match self {
+            CubeLocationType::Vertex => 3,
+            CubeLocationType::Edge => 2,
+            CubeLocationType::Face => 4,
+            CubeLocationType::Interior => 24,
+        }
+    }
+}
+
+impl CubeStructure {
+    pub fn new(
+        width: usize,
+        height: usize,
+        depth: usize,
+        outer_types: usize,
+        inner_types: usize,
+    ) -> Self {
+        if width <= 1 || height <= 1 || depth <= 1 {
+            panic!("Width, height and depth must be greater than 1");
+        }
+        if outer_types == 0 || inner_types == 0 {
+            panic!("There must be at least one outer and inner join type");
+        }
+
+        let size = [width, height, depth];
+        let outside =
+            |cell: [usize; 3], axis: usize| cell[axis] == 0 || cell[axis] == size[axis] - 1;
+        let grid: Vec<Vec<Vec<CubeGridLocation>>> = (0..width)
+            .map(|x| {
+                (0..height)
+                    .map(|y| {
+                        (0..depth)
+                            .map(|z| {
+                                let cell = [x, y, z];
+                                let joins: Vec<CubeJoin> = CUBE_COORDINATES
+                                    .iter()
+                                    .enumerate()
+                                    .filter_map(|(direction, coordinate)| {
+                                        let test_x = x.checked_add_signed(coordinate.0)?;
+                                        let test_y = y.checked_add_signed(coordinate.1)?;
+                                        let test_z = z.checked_add_signed(coordinate.2)?;
+                                        if test_x >= width || test_y >= height || test_z >= depth {
+                                            return None;
+                                        }
+                                        // Both cells share their position on the other axes.
+                                        let axis = direction % 3;
+                                        let outer = (0..3)
+                                            .filter(|other| *other != axis)
+                                            .any(|other| outside(cell, other));
+                                        Some(CubeJoin {
+                                            x: test_x,
+                                            y: test_y,
+                                            z: test_z,
+                                            join_type: if outer {
+                                                CubeJoinType::Outer
+                                            } else {
+                                                CubeJoinType::Inner
+                                            },
+                                        })
+                                    })
+                                    .collect();
+
+                                let location_type =
+                                    match (0..3).filter(|axis| outside(cell, *axis)).count() {
+                                        0 => CubeLocationType::Interior,
+                                        1 => CubeLocationType::Face,
+                                        2 => CubeLocationType::Edge,
+                                        _ => CubeLocationType::Vertex,
+                                    };
+
+                                CubeGridLocation {
+                                    x,
+                                    y,
+                                    z,
+                                    location_type,
+                                    joins,
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let count = |location_type: CubeLocationType| {
+            grid.iter()
+                .flatten()
+                .flatten()
+                .filter(|location| location.location_type == location_type)
+                .count()
+        };
+        let joins = |join_type: CubeJoinType| {
+            grid.iter()
+                .flatten()
+                .flatten()
+                .flat_map(|location| location.joins.iter())
+                .filter(|join| join.join_type == join_type)
+                .count()
+                / 2
+        };
+        let vertices = count(CubeLocationType::Vertex);
+        let edges = count(CubeLocationType::Edge);
+        let faces = count(CubeLocationType::Face);
+        let interiors = count(CubeLocationType::Interior);
+        let outer_joins = joins(CubeJoinType::Outer);
+        let inner_joins = joins(CubeJoinType::Inner);
+
+        let outer_join_counts: Vec<usize> = (0..outer_types)
+            .map(|outer_index| (outer_joins + outer_types - outer_index - 1) / outer_types)
+            .collect();
+        let inner_join_counts: Vec<usize> = (0..inner_types)
+            .map(|inner_index| (inner_joins + inner_types - inner_index - 1) / inner_types)
+            .collect();
+
+        CubeStructure {
+            width,
+            height,
+            depth,
+            outer_types,
+            inner_types,
+            grid,
+            vertices,
+            edges,
+            faces,
+            interiors,
+            outer_joins,
+            inner_joins,
+            outer_join_counts,
+            inner_join_counts,
+        }
+    }
+
+    /// Number of cubes in the box.
+    pub fn cells(&self) -> usize {
+        self.width * self.height * self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CubeLocationType, CubeStructure};
+
+    #[test]
+    fn test_build_structure() {
+        let cube_structure = CubeStructure::new(4, 3, 5, 2, 3);
+        assert_eq!(cube_structure.vertices, 8);
+        assert_eq!(cube_structure.edges, 4 * (2 + 1 + 3));
+        assert_eq!(cube_structure.faces, 2 * (2 + 6 + 3));
+        assert_eq!(cube_structure.interiors, 2 * 3);
+        assert_eq!(
+            cube_structure.outer_joins + cube_structure.inner_joins,
+            3 * 3 * 5 + 4 * 2 * 5 + 4 * 3 * 4
+        );
+        // Joins in the plane of each side of the box, less those along the box
+        // edges that two sides share.
+        assert_eq!(
+            cube_structure.outer_joins,
+            2 * (17 + 22 + 31) - 4 * (3 + 2 + 4)
+        );
+        assert_eq!(
+            cube_structure.grid[1][1][1].location_type,
+            CubeLocationType::Interior
+        );
+        assert_eq!(cube_structure.outer_join_counts, vec![52, 52]);
+    }
+}
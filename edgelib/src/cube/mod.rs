@@ -0,0 +1,9 @@
+mod cube_combinations;
+mod cube_order;
+mod cube_progress;
+mod cube_structure;
+
+pub use cube_combinations::*;
+pub use cube_order::*;
+pub use cube_progress::*;
+pub use cube_structure::*;
@@ -0,0 +1,214 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::model::{COORDINATES, JoinType, Lattice, PuzzleStructure};
+
+use super::{FRAME, Piece, Placement, PuzzleInstance};
+
+/// A shuffled puzzle together with the arrangement it was cut from.
+#[derive(Clone, Debug)]
+pub struct GeneratedPuzzle {
+    pub puzzle_instance: PuzzleInstance,
+    /// The same pieces with every piece fixed in its solved place.
+    pub solution: PuzzleInstance,
+}
+
+impl GeneratedPuzzle {
+    /// Colours every join of the structure, using `border_join_counts` and
+    /// `middle_join_counts` for how often each colour appears, then cuts the
+    /// grid into pieces which are shuffled and turned at random. Border
+    /// colours are numbered from 1 and middle colours follow them.
+    pub fn new(puzzle_structure: &PuzzleStructure, seed: u64) -> Result<Self, String> {
+        if puzzle_structure.lattice != Lattice::Square {
+            return Err("Only square pieces can be generated".to_string());
+        }
+        if puzzle_structure.border_join_counts.iter().sum::<usize>()
+            != puzzle_structure.border_joins
+            || puzzle_structure.middle_join_counts.iter().sum::<usize>()
+                != puzzle_structure.middle_joins
+        {
+            return Err(format!(
+                "Join counts must add up to {} border and {} middle joins",
+                puzzle_structure.border_joins, puzzle_structure.middle_joins
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut border_colours = colour_bag(&puzzle_structure.border_join_counts, 1);
+        let mut middle_colours = colour_bag(
+            &puzzle_structure.middle_join_counts,
+            1 + puzzle_structure.border_join_counts.len(),
+        );
+        border_colours.shuffle(&mut rng);
+        middle_colours.shuffle(&mut rng);
+
+        let solved = solved_sides(puzzle_structure, border_colours, middle_colours);
+        let sides = COORDINATES.len();
+        let mut placed: Vec<(Piece, Placement)> = puzzle_structure
+            .locations()
+            .zip(solved.iter())
+            .map(|(location, solved)| {
+                let rotation = rng.random_range(0..sides);
+                let mut turned: [usize; 4] = [FRAME; 4];
+                (0..sides).for_each(|side| turned[side] = solved[(side + rotation) % sides]);
+                (
+                    Piece::new(turned),
+                    Placement {
+                        x: location.x,
+                        y: location.y,
+                        rotation,
+                    },
+                )
+            })
+            .collect();
+        placed.shuffle(&mut rng);
+
+        let pieces: Vec<Piece> = placed.iter().map(|(piece, _)| piece.clone()).collect();
+        let puzzle_instance =
+            PuzzleInstance::new(puzzle_structure.width, puzzle_structure.height, pieces);
+        let mut solution = puzzle_instance.clone();
+        solution
+            .pieces
+            .iter_mut()
+            .zip(placed.iter())
+            .for_each(|(piece, (_, placement))| piece.fixed = Some(*placement));
+
+        Ok(GeneratedPuzzle {
+            puzzle_instance,
+            solution,
+        })
+    }
+}
+
+/// Colours every join, taking border and middle colours from the end of
+/// each list, and returns the sides of each cell's piece as it sits in the
+/// solution, in the order of `PuzzleStructure::locations`.
+pub(crate) fn solved_sides(
+    puzzle_structure: &PuzzleStructure,
+    mut border_colours: Vec<usize>,
+    mut middle_colours: Vec<usize>,
+) -> Vec<[usize; 4]> {
+    let sides = COORDINATES.len();
+    let mut colours: Vec<Vec<[usize; 4]>> =
+        vec![vec![[FRAME; 4]; puzzle_structure.height]; puzzle_structure.width];
+    puzzle_structure.locations().for_each(|location| {
+        (0..sides).for_each(|direction| {
+            let Some((test_x, test_y)) =
+                puzzle_structure.neighbour(location.x, location.y, direction)
+            else {
+                return;
+            };
+            if (test_x, test_y) < (location.x, location.y) {
+                return;
+            }
+            let join = location
+                .joins
+                .iter()
+                .find(|join| join.x == test_x && join.y == test_y)
+                .expect("Neighbour missing from joins");
+            let colour = match join.join_type {
+                JoinType::Border => border_colours.pop(),
+                JoinType::Middle => middle_colours.pop(),
+            }
+            .expect("Ran out of join colours");
+            colours[location.x][location.y][direction] = colour;
+            colours[test_x][test_y][(direction + sides / 2) % sides] = colour;
+        });
+    });
+    puzzle_structure
+        .locations()
+        .map(|location| colours[location.x][location.y])
+        .collect()
+}
+
+pub(crate) fn colour_bag(join_counts: &[usize], first_colour: usize) -> Vec<usize> {
+    join_counts
+        .iter()
+        .enumerate()
+        .flat_map(|(index, count)| std::iter::repeat_n(first_colour + index, *count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeneratedPuzzle;
+    use crate::model::{BoardShape, PuzzleStructure, SearchOption, SearchOrder};
+    use crate::solver::BacktrackSolver;
+
+    #[test]
+    fn test_generate() {
+        let puzzle_structure = PuzzleStructure::new(4, 3, 3, 2);
+        let generated = GeneratedPuzzle::new(&puzzle_structure, 7).unwrap();
+        let derived = generated.puzzle_instance.structure().unwrap();
+        assert_eq!(derived.border_joins, puzzle_structure.border_joins);
+        assert_eq!(
+            derived.border_join_counts.iter().sum::<usize>(),
+            puzzle_structure.border_joins
+        );
+        assert_eq!(
+            generated.solution.check(&puzzle_structure),
+            Ok(()),
+            "Solution must fit the frame"
+        );
+
+        let search_order = SearchOrder::new(4, 3, SearchOption::ScanRows);
+        let mut solver =
+            BacktrackSolver::new(&generated.solution, &puzzle_structure, &search_order);
+        assert_eq!(solver.solve(None, None).solutions.len(), 1);
+
+        let mut solver =
+            BacktrackSolver::new(&generated.puzzle_instance, &puzzle_structure, &search_order);
+        assert!(!solver.solve(Some(1), None).solutions.is_empty());
+    }
+
+    #[test]
+    fn test_seeded() {
+        let puzzle_structure = PuzzleStructure::new(5, 5, 2, 3);
+        let first = GeneratedPuzzle::new(&puzzle_structure, 3).unwrap();
+        let second = GeneratedPuzzle::new(&puzzle_structure, 3).unwrap();
+        assert_eq!(first.puzzle_instance, second.puzzle_instance);
+    }
+
+    #[test]
+    fn test_generate_shapes() {
+        [
+            "###\n#..\n",
+            "##..\n##..\n####\n####\n",
+            ".#.\n###\n.#.\n",
+            "####\n#..#\n####\n",
+        ]
+        .iter()
+        .for_each(|art| {
+            let shape = BoardShape::parse(art).unwrap();
+            let puzzle_structure = PuzzleStructure::from_shape(&shape, 2, 2);
+            let generated = GeneratedPuzzle::new(&puzzle_structure, 5).unwrap();
+            assert_eq!(
+                generated.solution.check(&puzzle_structure),
+                Ok(()),
+                "{}",
+                art
+            );
+            assert_eq!(
+                generated.puzzle_instance.check(&puzzle_structure),
+                Ok(()),
+                "{}",
+                art
+            );
+            let derived = generated
+                .puzzle_instance
+                .structure_for_shape(&shape)
+                .unwrap();
+            assert_eq!(derived.border_joins, puzzle_structure.border_joins);
+            assert_eq!(derived.middle_joins, puzzle_structure.middle_joins);
+            assert!(generated.puzzle_instance.structure().is_err());
+
+            let search_order =
+                SearchOrder::for_structure(&puzzle_structure, SearchOption::ScanRows);
+            let mut solver =
+                BacktrackSolver::new(&generated.puzzle_instance, &puzzle_structure, &search_order);
+            assert!(!solver.solve(Some(1), None).solutions.is_empty(), "{}", art);
+        });
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use crate::model::{
+    COORDINATES, Combinatorics, FastCombinations, JoinType, NodeCombinatorics, PuzzleStructure,
+    SearchProgress,
+};
+
+use super::{FRAME, Piece, PuzzleInstance};
+
+/// Combinatorics of a particular piece set. The generic model treats every
+/// side as an independent draw from the colour counts. Here each placement
+/// is instead scored with the colours the free pieces really show on the
+/// sides that meet earlier neighbours, in every rotation that fits the
+/// frame, so colour pairs that sit on the same piece, repeated pieces and
+/// symmetric pieces all shape the estimate. The ratio of that match
+/// probability to the independent one corrects the generic profile at each
+/// depth, which still accounts for colours running out as pieces are used.
+///
+/// The correction averages over every free piece that fits the cell's frame,
+/// including pieces an earlier cell has already taken, so it is biased
+/// towards the whole bag. On generated puzzles, whose colours are drawn
+/// independently, it is close to zero, and like the generic profile it
+/// undercounts the nodes a solver visits by about 0.1 log10 in total, as a
+/// generated puzzle always has a solution.
+pub struct InstanceCombinations<'a> {
+    puzzle_instance: &'a PuzzleInstance,
+    puzzle_structure: &'a PuzzleStructure,
+    puzzle_combinations: FastCombinations,
+    /// Share of the sides of each join type that carry each colour.
+    frequency: HashMap<usize, f64>,
+    /// Chance that two sides drawn independently match, by join type.
+    border_match: f64,
+    middle_match: f64,
+}
+
+impl<'a> InstanceCombinations<'a> {
+    pub fn new(
+        puzzle_instance: &'a PuzzleInstance,
+        puzzle_structure: &'a PuzzleStructure,
+    ) -> Result<Self, String> {
+        puzzle_instance.check(puzzle_structure)?;
+        // Corrections skip hinted cells, so each must hold a known piece.
+        if let Some(hint) = puzzle_structure.locations().find(|location| {
+            location.hint
+                && !puzzle_instance.pieces.iter().any(|piece| {
+                    piece
+                        .fixed
+                        .is_some_and(|fixed| fixed.x == location.x && fixed.y == location.y)
+                })
+        }) {
+            return Err(format!(
+                "Hint ({}, {}) has no fixed piece in the instance",
+                hint.x, hint.y
+            ));
+        }
+
+        let mut sides: HashMap<usize, usize> = HashMap::new();
+        puzzle_instance
+            .pieces
+            .iter()
+            .flat_map(|piece| piece.sides.iter())
+            .filter(|colour| **colour != FRAME)
+            .for_each(|colour| *sides.entry(*colour).or_insert(0) += 1);
+        let border_colours = puzzle_instance.border_colours();
+        let total = |border: bool| -> usize {
+            sides
+                .iter()
+                .filter(|(colour, _)| border_colours.contains(colour) == border)
+                .map(|(_, count)| count)
+                .sum()
+        };
+        let (border_sides, middle_sides) = (total(true), total(false));
+        let frequency: HashMap<usize, f64> = sides
+            .iter()
+            .map(|(colour, count)| {
+                let total = if border_colours.contains(colour) {
+                    border_sides
+                } else {
+                    middle_sides
+                };
+                (*colour, *count as f64 / total as f64)
+            })
+            .collect();
+        let matching = |border: bool| -> f64 {
+            frequency
+                .iter()
+                .filter(|(colour, _)| border_colours.contains(colour) == border)
+                .map(|(_, share)| share * share)
+                .sum()
+        };
+
+        Ok(InstanceCombinations {
+            puzzle_instance,
+            puzzle_structure,
+            puzzle_combinations: FastCombinations::new(puzzle_structure),
+            border_match: matching(true),
+            middle_match: matching(false),
+            frequency,
+        })
+    }
+
+    /// log10 of how much likelier a free piece is to match the filled
+    /// neighbours of the cell than independent colours would be.
+    fn log10_correction(&self, x: usize, y: usize, filled: &[Vec<bool>]) -> f64 {
+        let puzzle_structure = self.puzzle_structure;
+        let known: Vec<usize> = (0..COORDINATES.len())
+            .filter(|direction| {
+                puzzle_structure
+                    .neighbour(x, y, *direction)
+                    .is_some_and(|(test_x, test_y)| filled[test_x][test_y])
+            })
+            .collect();
+        if known.is_empty() {
+            return 0.0;
+        }
+
+        let independent: f64 = puzzle_structure.grid[x][y]
+            .joins
+            .iter()
+            .filter(|join| filled[join.x][join.y])
+            .map(|join| match join.join_type {
+                JoinType::Border => self.border_match,
+                JoinType::Middle => self.middle_match,
+            })
+            .product();
+
+        let scores: Vec<f64> = self
+            .puzzle_instance
+            .pieces
+            .iter()
+            .filter(|piece| piece.fixed.is_none())
+            .flat_map(|piece| {
+                (0..COORDINATES.len())
+                    .filter(|rotation| {
+                        self.puzzle_instance
+                            .fits_frame(puzzle_structure, piece, x, y, *rotation)
+                    })
+                    .map(|rotation| self.score(piece, rotation, &known))
+                    .collect::<Vec<f64>>()
+            })
+            .collect();
+        let matching = scores.iter().sum::<f64>() / scores.len() as f64;
+        (matching / independent).log10()
+    }
+
+    /// Chance that the piece, turned `rotation` times, matches random sides
+    /// in each of the known directions.
+    fn score(&self, piece: &Piece, rotation: usize, known: &[usize]) -> f64 {
+        known
+            .iter()
+            .map(|direction| {
+                self.frequency
+                    .get(&piece.side(*direction, rotation))
+                    .copied()
+                    .unwrap_or(0.0)
+            })
+            .product()
+    }
+}
+
+impl Combinatorics for InstanceCombinations<'_> {
+    type Progress = SearchProgress;
+
+    /// The generic count at each depth, corrected for the colours of the
+    /// pieces that could fill each cell of the order in turn.
+    fn log10_depths(&self, search_progress: &SearchProgress) -> Vec<f64> {
+        let mut filled: Vec<Vec<bool>> = self
+            .puzzle_structure
+            .grid
+            .iter()
+            .map(|column| column.iter().map(|location| location.hint).collect())
+            .collect();
+
+        let mut correction = 0.0;
+        let mut corrections: Vec<f64> = vec![correction];
+        search_progress.cells.iter().for_each(|location| {
+            correction += self.log10_correction(location.x, location.y, &filled);
+            corrections.push(correction);
+            filled[location.x][location.y] = true;
+        });
+
+        search_progress
+            .progress
+            .iter()
+            .zip(corrections)
+            .map(|(used, correction)| self.puzzle_combinations.log10_nodes(used) + correction)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InstanceCombinations;
+    use crate::instance::{GeneratedPuzzle, Piece, PuzzleInstance};
+    use crate::model::{
+        FastCombinations, Location, PuzzleStructure, SearchNodes, SearchOption, SearchOrder,
+        SearchProgress,
+    };
+    use crate::solver::BacktrackSolver;
+
+    #[test]
+    fn test_single_colours() {
+        let puzzle_structure = PuzzleStructure::new(4, 4, 1, 1);
+        let generated = GeneratedPuzzle::new(&puzzle_structure, 3).unwrap();
+        let instance_combinations =
+            InstanceCombinations::new(&generated.puzzle_instance, &puzzle_structure).unwrap();
+        let search_order = SearchOrder::new(4, 4, SearchOption::SpiralIn);
+        let search_nodes = SearchNodes::new(
+            &instance_combinations,
+            &SearchProgress::new(&puzzle_structure, &search_order),
+        );
+        let generic = SearchNodes::new(
+            &FastCombinations::new(&puzzle_structure),
+            &SearchProgress::new(&puzzle_structure, &search_order),
+        );
+        search_nodes
+            .nodes
+            .iter()
+            .zip(generic.nodes.iter())
+            .for_each(|(instance, generic)| assert!((instance - generic).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_colour_frequencies() {
+        // Four border joins of colour 1, two of colour 2 and one middle join.
+        let puzzle_instance = PuzzleInstance::new(
+            3,
+            2,
+            vec![
+                Piece::new([1, 1, 0, 0]),
+                Piece::new([1, 3, 1, 0]),
+                Piece::new([0, 1, 1, 0]),
+                Piece::new([2, 0, 0, 1]),
+                Piece::new([2, 0, 2, 3]),
+                Piece::new([0, 0, 2, 1]),
+            ],
+        );
+        let mut puzzle_structure = puzzle_instance.structure().unwrap();
+        // A hint needs a fixed piece for the corrections to skip its cell.
+        puzzle_structure
+            .set_hints(&[Location { x: 0, y: 0 }])
+            .unwrap();
+        assert!(
+            InstanceCombinations::new(&puzzle_instance, &puzzle_structure)
+                .is_err_and(|error| error.contains("no fixed piece"))
+        );
+        puzzle_structure.set_hints(&[]).unwrap();
+        let instance_combinations =
+            InstanceCombinations::new(&puzzle_instance, &puzzle_structure).unwrap();
+        let search_order = SearchOrder::new(3, 2, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let nodes = SearchNodes::new(&instance_combinations, &search_progress).nodes;
+        let generic =
+            SearchNodes::new(&FastCombinations::new(&puzzle_structure), &search_progress).nodes;
+
+        // Each edge piece offers one side of each colour against the 2/3 and
+        // 1/3 split, a half chance where independent sides match 5/9 of the time.
+        assert!((nodes[2] - generic[2] - 0.9f64.log10()).abs() < 1e-9);
+        let corrections: f64 = 0.9 * (21.0 / 20.0) * (21.0 / 20.0) * 0.9 * (27.0 / 25.0);
+        assert!((nodes[6] - generic[6] - corrections.log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_solver_nodes() {
+        let puzzle_structure = PuzzleStructure::new(4, 4, 2, 3);
+        let search_order = SearchOrder::new(4, 4, SearchOption::SpiralIn);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let generic = SearchNodes::new(&FastCombinations::new(&puzzle_structure), &search_progress);
+
+        let seeds = 8;
+        let (mut estimated, mut visited) = (0.0, 0.0);
+        (0..seeds).for_each(|seed| {
+            let generated = GeneratedPuzzle::new(&puzzle_structure, seed).unwrap();
+            let instance_combinations =
+                InstanceCombinations::new(&generated.puzzle_instance, &puzzle_structure).unwrap();
+            let search_nodes = SearchNodes::new(&instance_combinations, &search_progress);
+            assert!((search_nodes.log10_total() - generic.log10_total()).abs() < 0.05);
+
+            let mut solver =
+                BacktrackSolver::new(&generated.puzzle_instance, &puzzle_structure, &search_order);
+            let nodes: u64 = solver.solve(None, None).nodes.iter().sum();
+            estimated += search_nodes.log10_total();
+            visited += (nodes as f64).log10();
+        });
+        // The estimate falls short of the nodes visited, but not by much.
+        let bias = (visited - estimated) / seeds as f64;
+        assert!(bias > 0.0 && bias < 0.2);
+    }
+}
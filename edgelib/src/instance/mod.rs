@@ -0,0 +1,8 @@
+mod generator;
+mod instance_combinations;
+mod piece_file;
+mod puzzle_instance;
+
+pub use generator::*;
+pub use instance_combinations::*;
+pub use puzzle_instance::*;
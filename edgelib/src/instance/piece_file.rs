@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+
+use crate::model::{BoardShape, Location, PuzzleStructure};
+
+use super::{FRAME, Piece, Placement, PuzzleInstance};
+
+// Piece files list one piece per line as four colours in the order top,
+// right, bottom, left with 0 for the frame, the usual Eternity II layout.
+// An optional first line of two numbers gives the width and height, and
+// lines of the form `hint <piece> <x> <y> <rotation>` fix the numbered
+// piece (counting from 1) turned clockwise `rotation` times. Text after
+// `#` is ignored.
+
+/// Side index, as used by `Piece::sides`, of each colour in a file line.
+const FILE_ORDER: [usize; 4] = [3, 0, 1, 2];
+
+impl PuzzleInstance {
+    pub fn read(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("Unable to read {}: {}", path, error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text())
+            .map_err(|error| format!("Unable to write {}: {}", path, error))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut size: Option<(usize, usize)> = None;
+        let mut pieces: Vec<Piece> = vec![];
+        let mut hints: Vec<(usize, Placement)> = vec![];
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = line_index + 1;
+            let content = line.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            let mut words: Vec<&str> = content
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|word| !word.is_empty())
+                .collect();
+            let is_hint = words[0].eq_ignore_ascii_case("hint");
+            if is_hint {
+                words.remove(0);
+            }
+            let numbers: Vec<usize> = words
+                .iter()
+                .map(|word| word.parse::<usize>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Line {} is not a list of numbers", line_number))?;
+
+            if is_hint {
+                if numbers.len() != 4 {
+                    return Err(format!(
+                        "Line {} should be hint <piece> <x> <y> <rotation>",
+                        line_number
+                    ));
+                }
+                hints.push((
+                    numbers[0],
+                    Placement {
+                        x: numbers[1],
+                        y: numbers[2],
+                        rotation: numbers[3] % 4,
+                    },
+                ));
+            } else if numbers.len() == 2 && pieces.is_empty() && size.is_none() {
+                size = Some((numbers[0], numbers[1]));
+            } else if numbers.len() == 4 {
+                let mut sides: [usize; 4] = [FRAME; 4];
+                FILE_ORDER
+                    .iter()
+                    .zip(numbers.iter())
+                    .for_each(|(side, colour)| sides[*side] = *colour);
+                pieces.push(Piece::new(sides));
+            } else {
+                return Err(format!(
+                    "Line {} should hold the four colours of a piece",
+                    line_number
+                ));
+            }
+        }
+
+        for (number, placement) in hints {
+            if number == 0 || number > pieces.len() {
+                return Err(format!("Hint refers to missing piece {}", number));
+            }
+            pieces[number - 1].fixed = Some(placement);
+        }
+
+        let (width, height) = match size {
+            Some(size) => size,
+            None => infer_size(&pieces)?,
+        };
+        if width <= 1 || height <= 1 {
+            return Err("Width and height must be greater than 1".to_string());
+        }
+
+        Ok(PuzzleInstance::new(width, height, pieces))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {}\n", self.width, self.height);
+        self.pieces.iter().for_each(|piece| {
+            let colours: Vec<String> = FILE_ORDER
+                .iter()
+                .map(|side| piece.sides[*side].to_string())
+                .collect();
+            text.push_str(&colours.join(" "));
+            text.push('\n');
+        });
+        self.pieces.iter().enumerate().for_each(|(index, piece)| {
+            if let Some(placement) = piece.fixed {
+                text.push_str(&format!(
+                    "hint {} {} {} {}\n",
+                    index + 1,
+                    placement.x,
+                    placement.y,
+                    placement.rotation
+                ));
+            }
+        });
+        text
+    }
+
+    /// Builds the structure this instance fills, with the join counts taken
+    /// from the colours on the pieces rather than an even split and the
+    /// fixed pieces set as hints. Piece files only give the width and height,
+    /// so the board is taken to be a full rectangle.
+    pub fn structure(&self) -> Result<PuzzleStructure, String> {
+        if self.pieces.len() != self.width * self.height {
+            return Err(format!(
+                "{} pieces do not fill a {}x{} rectangle, so the board shape must be given",
+                self.pieces.len(),
+                self.width,
+                self.height
+            ));
+        }
+        self.structure_for_shape(&BoardShape::rectangle(self.width, self.height))
+    }
+
+    /// Same as `structure` for the pieces of an irregular board.
+    pub fn structure_for_shape(&self, shape: &BoardShape) -> Result<PuzzleStructure, String> {
+        if shape.width != self.width || shape.height != self.height {
+            return Err(format!(
+                "Instance is {}x{} but the shape is {}x{}",
+                self.width, self.height, shape.width, shape.height
+            ));
+        }
+        let border_join_counts = join_counts(self, true)?;
+        let middle_join_counts = join_counts(self, false)?;
+
+        let mut puzzle_structure =
+            PuzzleStructure::from_shape(shape, border_join_counts.len(), middle_join_counts.len());
+        if border_join_counts.iter().sum::<usize>() != puzzle_structure.border_joins
+            || middle_join_counts.iter().sum::<usize>() != puzzle_structure.middle_joins
+        {
+            return Err(format!(
+                "Pieces have {} border and {} middle joins but the board has {} and {}",
+                border_join_counts.iter().sum::<usize>(),
+                middle_join_counts.iter().sum::<usize>(),
+                puzzle_structure.border_joins,
+                puzzle_structure.middle_joins
+            ));
+        }
+        puzzle_structure.border_join_counts = border_join_counts;
+        puzzle_structure.middle_join_counts = middle_join_counts;
+        self.check(&puzzle_structure)?;
+
+        let hints: Vec<Location> = self
+            .pieces
+            .iter()
+            .filter_map(|piece| piece.fixed)
+            .map(|placement| Location {
+                x: placement.x,
+                y: placement.y,
+            })
+            .collect();
+        puzzle_structure.set_hints(&hints)?;
+
+        Ok(puzzle_structure)
+    }
+}
+
+/// Number of joins of each colour, largest first. A colour appears on both
+/// sides of every join it is used on, so each colour must be seen an even
+/// number of times.
+fn join_counts(instance: &PuzzleInstance, border: bool) -> Result<Vec<usize>, String> {
+    let colours = if border {
+        instance.border_colours()
+    } else {
+        instance.middle_colours()
+    };
+    let mut sides: BTreeMap<usize, usize> = colours.iter().map(|colour| (*colour, 0)).collect();
+    instance.pieces.iter().for_each(|piece| {
+        piece.sides.iter().for_each(|colour| {
+            if let Some(count) = sides.get_mut(colour) {
+                *count += 1;
+            }
+        });
+    });
+
+    if let Some((colour, count)) = sides.iter().find(|(_, count)| **count % 2 != 0) {
+        return Err(format!(
+            "Colour {} is on {} sides so cannot be paired into joins",
+            colour, count
+        ));
+    }
+
+    let mut counts: Vec<usize> = sides.values().map(|count| count / 2).collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    if counts.is_empty() {
+        counts.push(0);
+    }
+    Ok(counts)
+}
+
+/// Works out the width and height from the number of pieces with one frame
+/// side, using the wider of the two possible layouts.
+fn infer_size(pieces: &[Piece]) -> Result<(usize, usize), String> {
+    let total = pieces.len();
+    let edges = pieces
+        .iter()
+        .filter(|piece| {
+            piece
+                .sides
+                .iter()
+                .filter(|colour| **colour == FRAME)
+                .count()
+                == 1
+        })
+        .count();
+    let perimeter = edges / 2 + 4;
+    (2..=perimeter / 2)
+        .map(|height| (perimeter - height, height))
+        .find(|(width, height)| width * height == total && 2 * (width + height) - 8 == edges)
+        .ok_or_else(|| format!("Cannot arrange {} pieces into a rectangle", total))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::{Piece, Placement, PuzzleInstance};
+
+    const THREE_BY_TWO: &str = "\
+# top right bottom left
+0 1 2 0
+0 3 4 1
+0 0 5 3
+2 6 0 0
+4 7 0 6
+5 0 0 7
+hint 5 1 1 0
+";
+
+    #[test]
+    fn test_parse_pieces() {
+        let instance = PuzzleInstance::parse(THREE_BY_TWO).unwrap();
+        assert_eq!(instance.width, 3);
+        assert_eq!(instance.height, 2);
+        assert_eq!(instance.pieces[0], Piece::new([1, 2, 0, 0]));
+        assert_eq!(
+            instance.pieces[4].fixed,
+            Some(Placement {
+                x: 1,
+                y: 1,
+                rotation: 0
+            })
+        );
+
+        let puzzle_structure = instance.structure().unwrap();
+        assert_eq!(puzzle_structure.border_types, 6);
+        assert_eq!(puzzle_structure.border_join_counts, vec![1; 6]);
+        assert_eq!(puzzle_structure.middle_join_counts, vec![1]);
+        assert_eq!(puzzle_structure.hints(), 1);
+        assert_eq!(puzzle_structure.edges, 1);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let instance = PuzzleInstance::parse(THREE_BY_TWO).unwrap();
+        assert_eq!(
+            PuzzleInstance::parse(&instance.to_text()).unwrap(),
+            instance
+        );
+    }
+
+    #[test]
+    fn test_odd_colour() {
+        let instance = PuzzleInstance::parse(&THREE_BY_TWO.replace("5 0 0 7", "5 0 0 8")).unwrap();
+        assert!(instance.structure().is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::{COORDINATES, Lattice, LocationType, PuzzleStructure, next_to_frame};
+
+/// Colour used on the sides of a piece that face the outside of the puzzle.
+pub const FRAME: usize = 0;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleInstance {
+    pub width: usize,
+    pub height: usize,
+    pub pieces: Vec<Piece>,
+}
+
+/// A physical piece. `sides` holds the colour facing each of the `COORDINATES`
+/// directions (right, down, left, up) when the piece is not rotated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Piece {
+    pub sides: [usize; 4],
+    pub fixed: Option<Placement>,
+}
+
+/// A cell and the number of clockwise quarter turns applied to a piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub x: usize,
+    pub y: usize,
+    pub rotation: usize,
+}
+
+impl Piece {
+    pub fn new(sides: [usize; 4]) -> Self {
+        Piece { sides, fixed: None }
+    }
+
+    pub fn fixed(sides: [usize; 4], placement: Placement) -> Self {
+        Piece {
+            sides,
+            fixed: Some(placement),
+        }
+    }
+
+    /// Colour facing `direction` once the piece is turned clockwise `rotation` times.
+    pub fn side(&self, direction: usize, rotation: usize) -> usize {
+        let count = self.sides.len();
+        self.sides[(direction + count - rotation % count) % count]
+    }
+
+    pub fn frame_sides(&self) -> usize {
+        self.sides.iter().filter(|colour| **colour == FRAME).count()
+    }
+
+    /// Which sides of the piece show the frame colour.
+    pub fn frame(&self) -> Vec<bool> {
+        self.sides.iter().map(|colour| *colour == FRAME).collect()
+    }
+
+    /// Location type this piece can fill, from its number of frame sides in
+    /// the same way as the cells of a `PuzzleStructure`.
+    pub fn location_type(&self) -> LocationType {
+        Lattice::Square.location_type(self.frame_sides())
+    }
+
+    /// True if a side of the piece touches one of its frame sides.
+    fn next_to_frame(&self, side: usize) -> bool {
+        next_to_frame(&self.frame(), side)
+    }
+}
+
+impl PuzzleInstance {
+    pub fn new(width: usize, height: usize, pieces: Vec<Piece>) -> Self {
+        if width <= 1 || height <= 1 {
+            panic!("Width and height must be greater than 1");
+        }
+
+        PuzzleInstance {
+            width,
+            height,
+            pieces,
+        }
+    }
+
+    /// Colours found next to a frame side on any piece. These are the
+    /// colours of border joins.
+    pub fn border_colours(&self) -> BTreeSet<usize> {
+        self.pieces
+            .iter()
+            .flat_map(|piece| {
+                (0..piece.sides.len())
+                    .filter(|side| piece.sides[*side] != FRAME && piece.next_to_frame(*side))
+                    .map(|side| piece.sides[side])
+            })
+            .collect()
+    }
+
+    /// Colours never found next to a frame side, which join in the middle.
+    pub fn middle_colours(&self) -> BTreeSet<usize> {
+        let border_colours = self.border_colours();
+        self.pieces
+            .iter()
+            .flat_map(|piece| piece.sides.iter().copied())
+            .filter(|colour| *colour != FRAME && !border_colours.contains(colour))
+            .collect()
+    }
+
+    /// True if the piece, turned `rotation` times, shows frame colours on
+    /// exactly the sides of the cell that face the outside.
+    pub fn fits_frame(
+        &self,
+        puzzle_structure: &PuzzleStructure,
+        piece: &Piece,
+        x: usize,
+        y: usize,
+        rotation: usize,
+    ) -> bool {
+        (0..COORDINATES.len()).all(|direction| {
+            let outside = puzzle_structure.neighbour(x, y, direction).is_none();
+            outside == (piece.side(direction, rotation) == FRAME)
+        })
+    }
+
+    /// Checks that the pieces can fill the puzzle described by the structure.
+    pub fn check(&self, puzzle_structure: &PuzzleStructure) -> Result<(), String> {
+        if puzzle_structure.lattice != Lattice::Square {
+            return Err("Instances only hold square pieces".to_string());
+        }
+        if self.width != puzzle_structure.width || self.height != puzzle_structure.height {
+            return Err(format!(
+                "Instance is {}x{} but the structure is {}x{}",
+                self.width, self.height, puzzle_structure.width, puzzle_structure.height
+            ));
+        }
+        if self.pieces.len() != puzzle_structure.cells() {
+            return Err(format!(
+                "Instance has {} pieces but needs {}",
+                self.pieces.len(),
+                puzzle_structure.cells()
+            ));
+        }
+
+        let mut counts: [usize; 3] = [0; 3];
+        self.pieces
+            .iter()
+            .for_each(|piece| match piece.location_type() {
+                LocationType::Corner => counts[0] += 1,
+                LocationType::Edge => counts[1] += 1,
+                LocationType::Interior => counts[2] += 1,
+            });
+        let expected: [usize; 3] = [
+            LocationType::Corner,
+            LocationType::Edge,
+            LocationType::Interior,
+        ]
+        .map(|location_type| {
+            puzzle_structure
+                .locations()
+                .filter(|location| location.location_type == location_type)
+                .count()
+        });
+        if counts != expected {
+            return Err(format!(
+                "Instance has {} corner, {} edge and {} interior pieces but the structure needs {}, {} and {}",
+                counts[0], counts[1], counts[2], expected[0], expected[1], expected[2]
+            ));
+        }
+
+        // Corners come in several layouts, such as two frame sides side by
+        // side or opposite each other, so match the layouts as well.
+        let mut piece_frames: Vec<Vec<bool>> = self
+            .pieces
+            .iter()
+            .map(|piece| least_rotation(&piece.frame()))
+            .collect();
+        let mut cell_frames: Vec<Vec<bool>> = puzzle_structure
+            .locations()
+            .map(|location| least_rotation(&puzzle_structure.frame(location.x, location.y)))
+            .collect();
+        piece_frames.sort();
+        cell_frames.sort();
+        if piece_frames != cell_frames {
+            return Err(
+                "Frame sides of the pieces do not match the cells of the board".to_string(),
+            );
+        }
+
+        // A border colour shows away from the frame only on the inner piece
+        // of a join by a concave corner.
+        let border_colours = self.border_colours();
+        let mut inner: BTreeMap<usize, usize> = BTreeMap::new();
+        self.pieces.iter().for_each(|piece| {
+            (0..piece.sides.len())
+                .filter(|side| {
+                    !piece.next_to_frame(*side) && border_colours.contains(&piece.sides[*side])
+                })
+                .for_each(|side| *inner.entry(piece.sides[side]).or_insert(0) += 1);
+        });
+        let inner_sides: usize = inner.values().sum();
+        let expected_sides = puzzle_structure.inner_border_sides();
+        if inner_sides != expected_sides {
+            let shared: Vec<usize> = inner.keys().copied().collect();
+            return Err(if expected_sides == 0 {
+                format!(
+                    "Colours {:?} are used both next to the frame and in the middle",
+                    shared
+                )
+            } else {
+                format!(
+                    "Border colours are on {} sides away from the frame but the board has {} by concave corners",
+                    inner_sides, expected_sides
+                )
+            });
+        }
+
+        let mut taken: Vec<Vec<bool>> = vec![vec![false; self.height]; self.width];
+        for (index, piece) in self.pieces.iter().enumerate() {
+            let Some(placement) = piece.fixed else {
+                continue;
+            };
+            if placement.x >= self.width || placement.y >= self.height {
+                return Err(format!(
+                    "Piece {} is fixed at ({}, {}) which is outside the puzzle",
+                    index, placement.x, placement.y
+                ));
+            }
+            if taken[placement.x][placement.y] {
+                return Err(format!(
+                    "More than one piece is fixed at ({}, {})",
+                    placement.x, placement.y
+                ));
+            }
+            taken[placement.x][placement.y] = true;
+            if !self.fits_frame(
+                puzzle_structure,
+                piece,
+                placement.x,
+                placement.y,
+                placement.rotation,
+            ) {
+                return Err(format!(
+                    "Piece {} does not fit the frame at ({}, {}) with rotation {}",
+                    index, placement.x, placement.y, placement.rotation
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The turn of a piece or cell's frame sides that sorts first, so that
+/// layouts can be compared whatever way round they are.
+fn least_rotation(frame: &[bool]) -> Vec<bool> {
+    (0..frame.len())
+        .map(|rotation| {
+            (0..frame.len())
+                .map(|side| frame[(side + rotation) % frame.len()])
+                .collect::<Vec<bool>>()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Piece, Placement, PuzzleInstance};
+    use crate::model::PuzzleStructure;
+
+    fn two_by_two() -> PuzzleInstance {
+        // Corners of a 2x2 puzzle as they sit unrotated at (0,0), (1,0), (1,1), (0,1).
+        PuzzleInstance::new(
+            2,
+            2,
+            vec![
+                Piece::new([1, 2, 0, 0]),
+                Piece::new([0, 3, 1, 0]),
+                Piece::new([0, 0, 4, 3]),
+                Piece::new([4, 0, 0, 2]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_check_instance() {
+        let puzzle_structure = PuzzleStructure::new(2, 2, 4, 1);
+        let mut instance = two_by_two();
+        assert_eq!(instance.check(&puzzle_structure), Ok(()));
+
+        instance.pieces[2].fixed = Some(Placement {
+            x: 1,
+            y: 1,
+            rotation: 0,
+        });
+        assert_eq!(instance.check(&puzzle_structure), Ok(()));
+
+        instance.pieces[2].fixed = Some(Placement {
+            x: 1,
+            y: 1,
+            rotation: 1,
+        });
+        assert!(instance.check(&puzzle_structure).is_err());
+    }
+
+    #[test]
+    fn test_check_counts() {
+        let puzzle_structure = PuzzleStructure::new(2, 2, 4, 1);
+        let mut instance = two_by_two();
+        instance.pieces[3] = Piece::new([4, 5, 6, 2]);
+        assert!(instance.check(&puzzle_structure).is_err());
+
+        instance.pieces[3] = Piece::new([0, 4, 0, 2]);
+        assert!(instance.check(&puzzle_structure).is_err());
+    }
+
+    #[test]
+    fn test_piece_rotation() {
+        let piece = Piece::new([1, 2, 3, 4]);
+        assert_eq!(piece.side(0, 0), 1);
+        assert_eq!(piece.side(1, 1), 1);
+        assert_eq!(piece.side(0, 1), 4);
+        assert_eq!(piece.side(2, 6), 1);
+    }
+}
@@ -1 +1,6 @@
+pub mod cube;
+pub mod instance;
 pub mod model;
+pub mod optimiser;
+pub mod solver;
+pub mod sweep;
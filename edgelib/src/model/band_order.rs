@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use super::{Location, SearchOrderFamily, SearchOrderStrategy};
+
+/// Corner of the board an order starts from, with `(0, 0)` top left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Whether an order runs along rows or down columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    Rows,
+    Columns,
+}
+
+/// Scans the board in bands of `band` rows or columns from a start corner.
+/// Within a band the order zig-zags across it, one cell of each row or
+/// column in turn, before stepping along. With `snake` set, every other band
+/// runs back the way the last one came. A band of 1 is a plain scan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BandOrder {
+    pub band: usize,
+    pub axis: Axis,
+    pub corner: Corner,
+    pub snake: bool,
+}
+
+const CORNER_NAMES: [(Corner, &str); 4] = [
+    (Corner::TopLeft, "tl"),
+    (Corner::TopRight, "tr"),
+    (Corner::BottomLeft, "bl"),
+    (Corner::BottomRight, "br"),
+];
+
+impl BandOrder {
+    pub fn new(band: usize, axis: Axis, corner: Corner, snake: bool) -> Self {
+        if band == 0 {
+            panic!("Bands must hold at least one row or column");
+        }
+        BandOrder {
+            band,
+            axis,
+            corner,
+            snake,
+        }
+    }
+
+    /// Reads a name of the form `band-<k>-<rows|columns>-<tl|tr|bl|br>[-snake]`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let parts: Vec<&str> = name.split('-').collect();
+        let (parts, snake) = match parts[..] {
+            [.., "snake"] => (&parts[..parts.len() - 1], true),
+            _ => (&parts[..], false),
+        };
+        let ["band", band, axis, corner] = parts[..] else {
+            return None;
+        };
+        let band = band.parse::<usize>().ok().filter(|band| *band > 0)?;
+        let axis = match axis {
+            "rows" => Axis::Rows,
+            "columns" => Axis::Columns,
+            _ => return None,
+        };
+        let (corner, _) = CORNER_NAMES.iter().find(|(_, text)| *text == corner)?;
+        Some(BandOrder::new(band, axis, *corner, snake))
+    }
+}
+
+impl SearchOrderStrategy for BandOrder {
+    fn name(&self) -> String {
+        let (_, corner) = CORNER_NAMES
+            .iter()
+            .find(|(corner, _)| *corner == self.corner)
+            .unwrap();
+        format!(
+            "band-{}-{}-{}{}",
+            self.band,
+            match self.axis {
+                Axis::Rows => "rows",
+                Axis::Columns => "columns",
+            },
+            corner,
+            if self.snake { "-snake" } else { "" }
+        )
+    }
+
+    fn order(&self, width: usize, height: usize) -> Vec<Location> {
+        // Lay out rows from the top left, with `along` the length of each
+        // row and `across` the number of rows, then turn to suit.
+        let (along, across) = match self.axis {
+            Axis::Rows => (width, height),
+            Axis::Columns => (height, width),
+        };
+        let mut order: Vec<Location> = vec![];
+        (0..across)
+            .step_by(self.band)
+            .enumerate()
+            .for_each(|(index, start)| {
+                let rows = start..(start + self.band).min(across);
+                let steps: Vec<usize> = if self.snake && index % 2 == 1 {
+                    (0..along).rev().collect()
+                } else {
+                    (0..along).collect()
+                };
+                steps.iter().for_each(|step| {
+                    rows.clone().for_each(|row| {
+                        order.push(match self.axis {
+                            Axis::Rows => Location { x: *step, y: row },
+                            Axis::Columns => Location { x: row, y: *step },
+                        })
+                    })
+                });
+            });
+
+        let (flip_x, flip_y) = match self.corner {
+            Corner::TopLeft => (false, false),
+            Corner::TopRight => (true, false),
+            Corner::BottomLeft => (false, true),
+            Corner::BottomRight => (true, true),
+        };
+        order
+            .into_iter()
+            .map(|location| Location {
+                x: if flip_x {
+                    width - 1 - location.x
+                } else {
+                    location.x
+                },
+                y: if flip_y {
+                    height - 1 - location.y
+                } else {
+                    location.y
+                },
+            })
+            .collect()
+    }
+}
+
+/// Every `BandOrder`, picked by name.
+pub struct BandFamily;
+
+impl SearchOrderFamily for BandFamily {
+    fn pattern(&self) -> String {
+        "band-<k>-<rows|columns>-<tl|tr|bl|br>[-snake]".to_string()
+    }
+
+    fn parse(&self, name: &str) -> Option<Arc<dyn SearchOrderStrategy>> {
+        BandOrder::parse(name)
+            .map(|band_order| Arc::new(band_order) as Arc<dyn SearchOrderStrategy>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Axis, BandOrder, Corner};
+    use crate::model::{Location, SearchOption, SearchOrder, SearchOrderStrategy};
+
+    #[test]
+    fn test_band_orders() {
+        let scan = BandOrder::parse("band-1-rows-tl").unwrap();
+        assert_eq!(
+            scan.order(4, 3),
+            SearchOrder::new(4, 3, SearchOption::ScanRows).order
+        );
+        let columns = BandOrder::new(1, Axis::Columns, Corner::TopLeft, false);
+        assert_eq!(
+            columns.order(4, 3),
+            SearchOrder::new(4, 3, SearchOption::ScanColumns).order
+        );
+
+        let band = BandOrder::parse("band-2-rows-br-snake").unwrap();
+        assert_eq!(
+            band,
+            BandOrder::new(2, Axis::Rows, Corner::BottomRight, true)
+        );
+        assert_eq!(band.name(), "band-2-rows-br-snake");
+        let cells: Vec<(usize, usize)> = band
+            .order(3, 3)
+            .iter()
+            .map(|Location { x, y }| (*x, *y))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                (2, 2),
+                (2, 1),
+                (1, 2),
+                (1, 1),
+                (0, 2),
+                (0, 1),
+                (0, 0),
+                (1, 0),
+                (2, 0)
+            ]
+        );
+
+        assert!(BandOrder::parse("band-0-rows-tl").is_none());
+        assert!(BandOrder::parse("band-2-rows-tm").is_none());
+        assert!(BandOrder::parse("band-2-diagonals-tl-snake").is_none());
+    }
+}
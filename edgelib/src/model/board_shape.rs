@@ -0,0 +1,203 @@
+use super::{COORDINATES, Location};
+
+/// Which cells of a `width` by `height` grid belong to the puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardShape {
+    pub width: usize,
+    pub height: usize,
+    /// Indexed `[x][y]` like `PuzzleStructure::grid`.
+    pub cells: Vec<Vec<bool>>,
+}
+
+// Shape files are either ASCII art, one row per line from the top, where `#`,
+// `X`, `O`, `*` or `1` marks a cell and any other character is a hole, or a
+// list of `x,y` cells with an optional header line.
+
+impl BoardShape {
+    pub fn rectangle(width: usize, height: usize) -> Self {
+        BoardShape {
+            width,
+            height,
+            cells: vec![vec![true; height]; width],
+        }
+    }
+
+    /// Shape holding exactly the given cells, sized to fit them.
+    pub fn from_locations(locations: &[Location]) -> Result<Self, String> {
+        if locations.is_empty() {
+            return Err("Shape has no cells".to_string());
+        }
+        let width = locations.iter().map(|location| location.x).max().unwrap() + 1;
+        let height = locations.iter().map(|location| location.y).max().unwrap() + 1;
+        let mut cells: Vec<Vec<bool>> = vec![vec![false; height]; width];
+        for location in locations {
+            if cells[location.x][location.y] {
+                return Err(format!(
+                    "Cell ({}, {}) appears more than once",
+                    location.x, location.y
+                ));
+            }
+            cells[location.x][location.y] = true;
+        }
+        BoardShape {
+            width,
+            height,
+            cells,
+        }
+        .validate()
+    }
+
+    pub fn read(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("Unable to read {}: {}", path, error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return Err("Shape has no cells".to_string());
+        }
+
+        let pairs: Vec<Option<Location>> = lines.iter().map(|line| parse_pair(line)).collect();
+        let header = usize::from(pairs[0].is_none() && pairs.len() > 1);
+        if pairs[header..].iter().all(Option::is_some) {
+            let locations: Vec<Location> = pairs.into_iter().flatten().collect();
+            return Self::from_locations(&locations);
+        }
+
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap();
+        let height = lines.len();
+        let mut cells: Vec<Vec<bool>> = vec![vec![false; height]; width];
+        lines.iter().enumerate().for_each(|(y, line)| {
+            line.chars().enumerate().for_each(|(x, c)| {
+                cells[x][y] = matches!(c, '#' | 'X' | 'x' | 'O' | 'o' | '*' | '1');
+            })
+        });
+        let shape = BoardShape {
+            width,
+            height,
+            cells,
+        };
+        if shape.size() == 0 {
+            return Err("Shape has no cells".to_string());
+        }
+        shape.validate()
+    }
+
+    /// Checks the shape spans at least two rows and two columns and that its
+    /// cells are all joined side to side.
+    fn validate(self) -> Result<Self, String> {
+        let span = |values: Vec<usize>| {
+            values.iter().max().unwrap_or(&0) + 1 - values.iter().min().unwrap_or(&0)
+        };
+        let width = span(self.locations().map(|location| location.x).collect());
+        let height = span(self.locations().map(|location| location.y).collect());
+        if width <= 1 || height <= 1 {
+            return Err(format!(
+                "Shape is {}x{} but must be at least 2 cells wide and 2 tall",
+                width, height
+            ));
+        }
+
+        let mut reached: Vec<Vec<bool>> = vec![vec![false; self.height]; self.width];
+        let mut stack: Vec<Location> = self.locations().take(1).collect();
+        while let Some(Location { x, y }) = stack.pop() {
+            if reached[x][y] {
+                continue;
+            }
+            reached[x][y] = true;
+            COORDINATES.iter().for_each(|(change_x, change_y)| {
+                let next_x = x.checked_add_signed(*change_x);
+                let next_y = y.checked_add_signed(*change_y);
+                if let (Some(x), Some(y)) = (next_x, next_y)
+                    && self.contains(x, y)
+                {
+                    stack.push(Location { x, y });
+                }
+            });
+        }
+        if let Some(location) = self
+            .locations()
+            .find(|location| !reached[location.x][location.y])
+        {
+            return Err(format!(
+                "Cell ({}, {}) is not joined to the rest of the shape",
+                location.x, location.y
+            ));
+        }
+        Ok(self)
+    }
+
+    /// ASCII art with `#` for cells and `.` for holes.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        (0..self.height).for_each(|y| {
+            (0..self.width).for_each(|x| text.push(if self.cells[x][y] { '#' } else { '.' }));
+            text.push('\n');
+        });
+        text
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[x][y]
+    }
+
+    /// Cells of the shape, column by column.
+    pub fn locations(&self) -> impl Iterator<Item = Location> + '_ {
+        (0..self.width).flat_map(move |x| {
+            (0..self.height)
+                .filter(move |y| self.cells[x][*y])
+                .map(move |y| Location { x, y })
+        })
+    }
+
+    /// Number of cells in the shape.
+    pub fn size(&self) -> usize {
+        self.cells.iter().flatten().filter(|cell| **cell).count()
+    }
+}
+
+fn parse_pair(line: &str) -> Option<Location> {
+    let values: Vec<usize> = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<usize>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match values[..] {
+        [x, y] => Some(Location { x, y }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoardShape;
+
+    #[test]
+    fn test_parse_shapes() {
+        let art = BoardShape::parse("###\n#.#\n###\n").unwrap();
+        assert_eq!(art.width, 3);
+        assert_eq!(art.height, 3);
+        assert_eq!(art.size(), 8);
+        assert!(!art.contains(1, 1));
+        assert_eq!(BoardShape::parse(&art.to_text()).unwrap(), art);
+
+        let list = BoardShape::parse("x,y\n0,0\n1,0\n0,1\n").unwrap();
+        assert_eq!(list.width, 2);
+        assert_eq!(list.height, 2);
+        assert!(list.contains(0, 1));
+        assert!(!list.contains(1, 1));
+
+        assert!(BoardShape::parse("...\n").is_err());
+        assert!(BoardShape::parse("0,0\n0,0\n").is_err());
+        assert!(BoardShape::parse("###\n").is_err());
+        assert!(BoardShape::parse("#.\n#.\n#.\n").is_err());
+        assert!(BoardShape::parse("##.\n##.\n..#\n").is_err());
+    }
+}
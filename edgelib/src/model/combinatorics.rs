@@ -0,0 +1,289 @@
+use super::{
+    FastCombinations, LocationType, Orientations, PieceClass, PuzzleCombinations, SearchProgress,
+    Used, add_log10s, generate_factorials,
+};
+
+/// What a search has placed at each depth, as `SearchNodes` reads it.
+pub trait Progress {
+    /// Counts of the pieces and joins placed by a depth.
+    type Used;
+
+    /// What is placed at each depth, starting with the empty board.
+    fn progress(&self) -> &[Self::Used];
+}
+
+impl Progress for SearchProgress {
+    type Used = Used;
+
+    fn progress(&self) -> &[Used] {
+        &self.progress
+    }
+}
+
+/// Expected partial solutions of a model of a puzzle. `SearchNodes`,
+/// summaries and sweeps only need this, so any model that can give it can
+/// reuse the rest of the pipeline.
+pub trait Combinatorics {
+    /// How a search of the model records its placements, such as
+    /// `SearchProgress` on a flat board.
+    type Progress: Progress;
+
+    /// log10 of the expected nodes at each depth of a search.
+    fn log10_depths(&self, progress: &Self::Progress) -> Vec<f64>;
+}
+
+/// Combinatorics that score each depth from its counts of pieces and joins
+/// alone, whichever cells they fill. Only these can score counts outside a
+/// search, as the optimisers and critical points do.
+pub trait NodeCombinatorics {
+    type Progress: Progress;
+
+    /// log10 of the expected number of partial solutions with these pieces and joins.
+    fn log10_nodes(&self, used: &<Self::Progress as Progress>::Used) -> f64;
+}
+
+impl<C: NodeCombinatorics + ?Sized> Combinatorics for C {
+    type Progress = C::Progress;
+
+    fn log10_depths(&self, progress: &Self::Progress) -> Vec<f64> {
+        progress
+            .progress()
+            .iter()
+            .map(|used| self.log10_nodes(used))
+            .collect()
+    }
+}
+
+/// Combinatorics of a flat board from log10 combination counts and match
+/// probabilities, indexed by how many of each piece class or join type have
+/// been used. From these it can also say how far the node counts of random
+/// puzzles spread around the expected counts, for `SearchSpread`.
+pub trait MomentCombinatorics: NodeCombinatorics<Progress = SearchProgress> {
+    /// Chance that every one of the first `n` middle joins matches.
+    fn log10_middle(&self) -> &[f64];
+    /// Chance that every one of the first `n` border joins matches.
+    fn log10_border(&self) -> &[f64];
+    /// Ways to lay `n` pieces of each piece class.
+    fn log10_pieces(&self) -> &[Vec<f64>];
+    /// Location type and frame layout of each piece class.
+    fn piece_classes(&self) -> &[PieceClass];
+    /// Ways a piece can be laid in a cell.
+    fn orientations(&self) -> Orientations;
+
+    /// log10 of `E[N^2] / E[N]^2`, where `N` is the number of partial
+    /// solutions with these pieces and joins across random puzzles.
+    ///
+    /// The default is a mean-field approximation rather than the true second
+    /// moment. Two placements that agree on `s` cells share the joins between
+    /// those cells, so once one is valid the other is more likely to be. The
+    /// sum runs over how many corner, edge and interior cells agree, taking
+    /// the expected number of shared border and middle joins, rounded, as if
+    /// the agreeing cells were spread evenly over the placed ones. It is
+    /// exact when no cells agree, giving 1, and when all do, giving
+    /// `1 / E[N]`, but joins between cells that happen to agree together are
+    /// miscounted in between. Solving every colouring of a 3x2 board shows
+    /// it overstating the true ratio by up to a third of a power of ten.
+    fn log10_moment_ratio(&self, used: &Used) -> f64 {
+        let orientations = self.orientations();
+        let cells = used.pieces.iter().copied().max().unwrap_or(0);
+        let f = generate_factorials(cells);
+        // log10 share of pairs that agree on exactly `s` of the `k` cells of
+        // each location type, over every way its piece classes split them.
+        let mut shares: Vec<Vec<f64>> = [used.corners, used.edges, used.interiors]
+            .iter()
+            .map(|k| {
+                let mut share = vec![f64::NEG_INFINITY; k + 1];
+                share[0] = 0.0;
+                share
+            })
+            .collect();
+        self.piece_classes()
+            .iter()
+            .zip(self.log10_pieces().iter())
+            .zip(used.pieces.iter())
+            .for_each(|((class, log10_pieces), k)| {
+                let n = log10_pieces.len() - 1;
+                let class_shares: Vec<f64> = (0..=*k)
+                    .map(|s| {
+                        let choices = (orientations.for_frame(&class.frame) * (n - s)) as f64;
+                        let differ = if s == *k {
+                            0.0
+                        } else if choices <= 1.0 {
+                            f64::NEG_INFINITY
+                        } else {
+                            ((k - s) as f64) * (1.0 - 1.0 / choices).log10()
+                        };
+                        f[*k] - f[s] - f[k - s] - log10_pieces[s] + differ
+                    })
+                    .collect();
+                let share = &mut shares[match class.location_type {
+                    LocationType::Corner => 0,
+                    LocationType::Edge => 1,
+                    LocationType::Interior => 2,
+                }];
+                *share = (0..share.len())
+                    .map(|agree| {
+                        let terms: Vec<f64> = (0..=agree.min(*k))
+                            .map(|s| share[agree - s] + class_shares[s])
+                            .filter(|term| *term > f64::NEG_INFINITY)
+                            .collect();
+                        add_log10s(&terms)
+                    })
+                    .collect();
+            });
+
+        let mut weights: Vec<f64> = vec![];
+        let mut terms: Vec<f64> = vec![];
+        (0..=used.corners).for_each(|corners| {
+            (0..=used.edges).for_each(|edges| {
+                (0..=used.interiors).for_each(|interiors| {
+                    let weight = shares[0][corners] + shares[1][edges] + shares[2][interiors];
+                    if weight == f64::NEG_INFINITY {
+                        return;
+                    }
+                    let frame = corners + edges;
+                    let borders = shared_joins(
+                        used.borders,
+                        [frame, frame.saturating_sub(1)],
+                        [
+                            used.corners + used.edges,
+                            (used.corners + used.edges).saturating_sub(1),
+                        ],
+                    );
+                    let middles = shared_joins(
+                        used.middles,
+                        [interiors, (interiors + edges).saturating_sub(1)],
+                        [
+                            used.interiors,
+                            (used.interiors + used.edges).saturating_sub(1),
+                        ],
+                    );
+                    weights.push(weight);
+                    terms
+                        .push(weight - self.log10_border()[borders] - self.log10_middle()[middles]);
+                })
+            })
+        });
+        add_log10s(&terms) - add_log10s(&weights)
+    }
+
+    /// log10 of `E[N^2] / E[N]^2` at each depth of a search. The default
+    /// takes `log10_moment_ratio` of each depth's counts. Backends that can
+    /// work out the true moment from the cells each depth fills override it,
+    /// and may refuse boards too large to work out.
+    fn log10_moment_ratios(&self, search_progress: &SearchProgress) -> Result<Vec<f64>, String> {
+        Ok(search_progress
+            .progress
+            .iter()
+            .map(|used| self.log10_moment_ratio(used))
+            .collect())
+    }
+}
+
+impl NodeCombinatorics for FastCombinations {
+    type Progress = SearchProgress;
+
+    fn log10_nodes(&self, used: &Used) -> f64 {
+        self.log10_pieces.iter().zip(used.pieces.iter()).fold(
+            self.log10_border[used.borders] + self.log10_middle[used.middles],
+            |log10_nodes, (log10_pieces, pieces)| log10_nodes + log10_pieces[*pieces],
+        )
+    }
+}
+
+impl MomentCombinatorics for FastCombinations {
+    fn log10_middle(&self) -> &[f64] {
+        &self.log10_middle
+    }
+
+    fn log10_border(&self) -> &[f64] {
+        &self.log10_border
+    }
+
+    fn log10_pieces(&self) -> &[Vec<f64>] {
+        &self.log10_pieces
+    }
+
+    fn piece_classes(&self) -> &[PieceClass] {
+        &self.piece_classes
+    }
+
+    fn orientations(&self) -> Orientations {
+        self.orientations
+    }
+}
+
+impl NodeCombinatorics for PuzzleCombinations {
+    type Progress = SearchProgress;
+
+    /// Taken from a single exact ratio rather than a sum of rounded logs.
+    fn log10_nodes(&self, used: &Used) -> f64 {
+        self.log10_exact_nodes(used)
+    }
+}
+
+impl MomentCombinatorics for PuzzleCombinations {
+    fn log10_middle(&self) -> &[f64] {
+        &self.log10_middle
+    }
+
+    fn log10_border(&self) -> &[f64] {
+        &self.log10_border
+    }
+
+    fn log10_pieces(&self) -> &[Vec<f64>] {
+        &self.log10_pieces
+    }
+
+    fn piece_classes(&self) -> &[PieceClass] {
+        &self.piece_classes
+    }
+
+    fn orientations(&self) -> Orientations {
+        self.orientations
+    }
+
+    fn log10_moment_ratios(&self, search_progress: &SearchProgress) -> Result<Vec<f64>, String> {
+        self.log10_exact_moment_ratios(search_progress)
+    }
+}
+
+/// Expected number of the `joins` whose two ends both agree, when each end
+/// agrees with chance `agree[end] / cells[end]`.
+fn shared_joins(joins: usize, agree: [usize; 2], cells: [usize; 2]) -> usize {
+    let chance: f64 = (0..2)
+        .map(|end| {
+            if cells[end] == 0 {
+                0.0
+            } else {
+                agree[end] as f64 / cells[end] as f64
+            }
+        })
+        .product();
+    (joins as f64 * chance).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeCombinatorics;
+    use crate::model::{
+        FastCombinations, PuzzleCombinations, PuzzleStructure, SearchNodes, SearchOption,
+        SearchOrder, SearchProgress,
+    };
+
+    #[test]
+    fn test_backends() {
+        let puzzle_structure = PuzzleStructure::new(5, 4, 2, 4);
+        let search_order = SearchOrder::new(5, 4, SearchOption::SpiralIn);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let backends: [Box<dyn NodeCombinatorics<Progress = SearchProgress>>; 2] = [
+            Box::new(FastCombinations::new(&puzzle_structure)),
+            Box::new(PuzzleCombinations::new(&puzzle_structure)),
+        ];
+        let [fast, exact] =
+            backends.map(|backend| SearchNodes::new(backend.as_ref(), &search_progress).nodes);
+        fast.iter()
+            .zip(exact.iter())
+            .for_each(|(fast, exact)| assert!((fast - exact).abs() < 1e-9));
+    }
+}
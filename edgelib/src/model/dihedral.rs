@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{Location, PuzzleStructure, SearchOrder, SearchOrderStrategy, SearchProgress, Used};
+
+/// The eight turns and flips of a grid. Quarter turns and the diagonal flips
+/// swap width and height, so they take an order laid out on the grid turned
+/// on its side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dihedral {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Dihedral {
+    pub fn all() -> Vec<Dihedral> {
+        vec![
+            Dihedral::Identity,
+            Dihedral::Rotate90,
+            Dihedral::Rotate180,
+            Dihedral::Rotate270,
+            Dihedral::FlipHorizontal,
+            Dihedral::FlipVertical,
+            Dihedral::Transpose,
+            Dihedral::AntiTranspose,
+        ]
+    }
+
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Dihedral::Rotate90
+                | Dihedral::Rotate270
+                | Dihedral::Transpose
+                | Dihedral::AntiTranspose
+        )
+    }
+
+    /// Where a cell of the source grid lands on a `width` by `height` grid.
+    /// The source grid is `height` by `width` when the transform swaps axes.
+    pub fn apply(&self, width: usize, height: usize, location: &Location) -> Location {
+        let Location { x, y } = *location;
+        let (x, y) = match self {
+            Dihedral::Identity => (x, y),
+            Dihedral::Rotate90 => (width - 1 - y, x),
+            Dihedral::Rotate180 => (width - 1 - x, height - 1 - y),
+            Dihedral::Rotate270 => (y, height - 1 - x),
+            Dihedral::FlipHorizontal => (width - 1 - x, y),
+            Dihedral::FlipVertical => (x, height - 1 - y),
+            Dihedral::Transpose => (y, x),
+            Dihedral::AntiTranspose => (width - 1 - y, height - 1 - x),
+        };
+        Location { x, y }
+    }
+}
+
+/// Another strategy's order, turned or flipped.
+pub struct TransformedOrder {
+    pub strategy: Arc<dyn SearchOrderStrategy>,
+    pub transform: Dihedral,
+}
+
+impl SearchOrderStrategy for TransformedOrder {
+    fn name(&self) -> String {
+        match self.transform {
+            Dihedral::Identity => self.strategy.name(),
+            transform => {
+                let mut name = self.strategy.name();
+                format!("{:?}", transform).chars().for_each(|letter| {
+                    if letter.is_uppercase()
+                        || (letter.is_ascii_digit() && name.ends_with(char::is_alphabetic))
+                    {
+                        name.push('-');
+                    }
+                    name.push(letter.to_ascii_lowercase());
+                });
+                name
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.transform {
+            Dihedral::Identity => self.strategy.title(),
+            transform => format!("{} {:?}", self.strategy.title(), transform),
+        }
+    }
+
+    fn order(&self, width: usize, height: usize) -> Vec<Location> {
+        let (source_width, source_height) = if self.transform.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        self.strategy
+            .order(source_width, source_height)
+            .iter()
+            .map(|location| self.transform.apply(width, height, location))
+            .collect()
+    }
+}
+
+/// The eight transforms of a strategy, leaving out any that give the same
+/// `SearchProgress` on the structure as one before it. Fails if the strategy
+/// does not give a valid order for the structure.
+pub fn distinct_transforms(
+    puzzle_structure: &PuzzleStructure,
+    strategy: &Arc<dyn SearchOrderStrategy>,
+) -> Result<Vec<Arc<dyn SearchOrderStrategy>>, String> {
+    let mut seen: HashSet<Vec<Used>> = HashSet::new();
+    let mut distinct: Vec<Arc<dyn SearchOrderStrategy>> = vec![];
+    for transform in Dihedral::all() {
+        let transformed: Arc<dyn SearchOrderStrategy> = Arc::new(TransformedOrder {
+            strategy: strategy.clone(),
+            transform,
+        });
+        let search_order = SearchOrder::from_strategy(puzzle_structure, transformed.as_ref())?;
+        if seen.insert(SearchProgress::new(puzzle_structure, &search_order).progress) {
+            distinct.push(transformed);
+        }
+    }
+    Ok(distinct)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Dihedral, TransformedOrder, distinct_transforms};
+    use crate::model::{Location, PuzzleStructure, SearchOption, SearchOrder, SearchOrderStrategy};
+
+    #[test]
+    fn test_transforms() {
+        Dihedral::all().into_iter().for_each(|transform| {
+            let transformed = TransformedOrder {
+                strategy: Arc::new(SearchOption::SpiralIn),
+                transform,
+            };
+            let search_order = SearchOrder::from_locations(5, 3, transformed.order(5, 3));
+            assert!(search_order.is_ok());
+        });
+        let rotated = TransformedOrder {
+            strategy: Arc::new(SearchOption::ScanRows),
+            transform: Dihedral::Rotate90,
+        };
+        assert_eq!(rotated.name(), "scan-rows-rotate-90");
+        assert_eq!(rotated.title(), "ScanRows Rotate90");
+        // Rows of the 3 by 5 grid become columns from the right.
+        assert_eq!(
+            rotated.order(5, 3)[..2],
+            [Location { x: 4, y: 0 }, Location { x: 4, y: 1 }]
+        );
+
+        // A row scan of a plain board looks the same from any corner, so only
+        // the row and column scans differ.
+        let puzzle_structure = PuzzleStructure::new(5, 3, 2, 3);
+        let strategy: Arc<dyn SearchOrderStrategy> = Arc::new(SearchOption::ScanRows);
+        let distinct = distinct_transforms(&puzzle_structure, &strategy).unwrap();
+        assert_eq!(distinct.len(), 2);
+        assert_eq!(distinct[0].title(), "ScanRows");
+        assert_eq!(distinct[1].title(), "ScanRows Rotate90");
+    }
+}
@@ -1,11 +1,12 @@
-use crate::model::PuzzleStructure;
+use crate::model::{Orientations, PieceClass, PuzzleStructure};
 
 pub struct FastCombinations {
     pub log10_middle: Vec<f64>,
     pub log10_border: Vec<f64>,
-    pub log10_corner: Vec<f64>,
-    pub log10_edge: Vec<f64>,
-    pub log10_interior: Vec<f64>,
+    /// Ways to lay `n` pieces of each piece class.
+    pub log10_pieces: Vec<Vec<f64>>,
+    pub piece_classes: Vec<PieceClass>,
+    pub orientations: Orientations,
 }
 
 impl FastCombinations {
@@ -15,35 +16,41 @@ impl FastCombinations {
         let size3: usize = puzzle_structure.corners;
         let size4: usize = puzzle_structure.edges;
         let size5: usize = puzzle_structure.interiors;
-        let max_size: usize = *(vec![size1, size2, size3, size4, size5]
-            .iter()
-            .max()
-            .unwrap());
+        let max_size: usize = *([size1, size2, size3, size4, size5].iter().max().unwrap());
         let f: Vec<f64> = generate_factorials(max_size);
         let p: Vec<Vec<f64>> = generate_permutions(&f);
         let c: Vec<Vec<f64>> = generate_combinations(&f);
 
         let log10_middle: Vec<f64> = middle_joins(&puzzle_structure.middle_join_counts, &p, &c);
         let log10_border: Vec<f64> = border_joins(&puzzle_structure.border_join_counts, &p, &c);
-        let log10_corner: Vec<f64> = p[puzzle_structure.corners].clone();
-        let log10_edge: Vec<f64> = p[puzzle_structure.edges].clone();
-        let log10_interior: Vec<f64> = p[puzzle_structure.interiors]
+        let orientations = puzzle_structure.orientations;
+        let log10_pieces: Vec<Vec<f64>> = puzzle_structure
+            .piece_classes
             .iter()
-            .enumerate()
-            .map(|(index, combinations)| combinations + 4f64.log10() * (index as f64))
+            .map(|class| oriented(&p[class.pieces], orientations.for_frame(&class.frame)))
             .collect();
 
         FastCombinations {
             log10_middle,
             log10_border,
-            log10_corner,
-            log10_edge,
-            log10_interior,
+            log10_pieces,
+            piece_classes: puzzle_structure.piece_classes.clone(),
+            orientations,
         }
     }
 }
 
-fn middle_joins(join_counts: &Vec<usize>, p: &Vec<Vec<f64>>, c: &Vec<Vec<f64>>) -> Vec<f64> {
+/// Permutations of pieces that can each be laid `orientations` ways.
+fn oriented(permutations: &[f64], orientations: usize) -> Vec<f64> {
+    let log10_orientations = (orientations as f64).log10();
+    permutations
+        .iter()
+        .enumerate()
+        .map(|(index, combinations)| combinations + log10_orientations * (index as f64))
+        .collect()
+}
+
+pub(crate) fn middle_joins(join_counts: &[usize], p: &[Vec<f64>], c: &[Vec<f64>]) -> Vec<f64> {
     let join_types: usize = join_counts.len();
     let total_joints: usize = join_counts.iter().sum();
 
@@ -75,7 +82,7 @@ fn middle_joins(join_counts: &Vec<usize>, p: &Vec<Vec<f64>>, c: &Vec<Vec<f64>>)
         .collect()
 }
 
-fn border_joins(join_counts: &Vec<usize>, p: &Vec<Vec<f64>>, c: &Vec<Vec<f64>>) -> Vec<f64> {
+fn border_joins(join_counts: &[usize], p: &[Vec<f64>], c: &[Vec<f64>]) -> Vec<f64> {
     let join_types: usize = join_counts.len();
     let total_joints: usize = join_counts.iter().sum();
 
@@ -103,11 +110,11 @@ fn border_joins(join_counts: &Vec<usize>, p: &Vec<Vec<f64>>, c: &Vec<Vec<f64>>)
     });
 
     (0..=total_joints)
-        .map(|b| (valid_combinations[join_types][b] - 2.0 * p[total_joints][b]))
+        .map(|b| valid_combinations[join_types][b] - 2.0 * p[total_joints][b])
         .collect()
 }
 
-fn add_log10s(values: &[f64]) -> f64 {
+pub(crate) fn add_log10s(values: &[f64]) -> f64 {
     if values.is_empty() {
         return f64::NEG_INFINITY;
     }
@@ -129,7 +136,7 @@ fn add_log10s(values: &[f64]) -> f64 {
 
     sum.log10() + reduce
 }
-fn generate_factorials(max_value: usize) -> Vec<f64> {
+pub(crate) fn generate_factorials(max_value: usize) -> Vec<f64> {
     let mut factorial: Vec<f64> = vec![0.0; max_value + 1];
     (1..=max_value).for_each(|index| {
         factorial[index] = factorial[index - 1] + (index as f64).log10();
@@ -137,14 +144,14 @@ fn generate_factorials(max_value: usize) -> Vec<f64> {
     factorial
 }
 
-fn generate_permutions(factorial: &Vec<f64>) -> Vec<Vec<f64>> {
+pub(crate) fn generate_permutions(factorial: &[f64]) -> Vec<Vec<f64>> {
     let max_value = factorial.len() - 1;
     (0..=max_value)
         .map(|n| (0..=n).map(|k| factorial[n] - factorial[n - k]).collect())
         .collect()
 }
 
-fn generate_combinations(factorial: &Vec<f64>) -> Vec<Vec<f64>> {
+pub(crate) fn generate_combinations(factorial: &[f64]) -> Vec<Vec<f64>> {
     let max_value = factorial.len() - 1;
     (0..=max_value)
         .map(|n| {
@@ -0,0 +1,195 @@
+use super::{COORDINATES, LocationType};
+
+/// Shape of the tiles and how the cells of the `x`, `y` grid touch.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Lattice {
+    /// Four-sided pieces in rows and columns.
+    #[default]
+    Square,
+    /// Six-sided pieces in rows, with every odd row shifted half a cell right.
+    Hexagonal,
+    /// Three-sided pieces in rows, pointing up where `x + y` is even and down
+    /// where it is odd.
+    Triangular,
+}
+
+const HEXAGONAL_EVEN: [(isize, isize); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1)];
+const HEXAGONAL_ODD: [(isize, isize); 6] = [(1, 0), (1, 1), (0, 1), (-1, 0), (0, -1), (1, -1)];
+const TRIANGULAR_UP: [(isize, isize); 3] = [(1, 0), (0, 1), (-1, 0)];
+const TRIANGULAR_DOWN: [(isize, isize); 3] = [(1, 0), (-1, 0), (0, -1)];
+
+impl Lattice {
+    /// Sides of each piece, which is also how many ways an interior piece can
+    /// be turned.
+    pub fn sides(&self) -> usize {
+        match self {
+            Lattice::Square => 4,
+            Lattice::Hexagonal => 6,
+            Lattice::Triangular => 3,
+        }
+    }
+
+    /// Step to the neighbour across each side of the cell, going round the
+    /// cell so that consecutive sides share a corner.
+    pub fn offsets(&self, x: usize, y: usize) -> &'static [(isize, isize)] {
+        match self {
+            Lattice::Square => &COORDINATES,
+            Lattice::Hexagonal if y.is_multiple_of(2) => &HEXAGONAL_EVEN,
+            Lattice::Hexagonal => &HEXAGONAL_ODD,
+            Lattice::Triangular if (x + y).is_multiple_of(2) => &TRIANGULAR_UP,
+            Lattice::Triangular => &TRIANGULAR_DOWN,
+        }
+    }
+
+    /// Kind of piece that fits a cell with `frame_sides` sides on the frame.
+    /// Hexagons along a straight side of the board show one or two sides to
+    /// the frame, so only those with three or more are corners. Pieces of a
+    /// kind whose frame sides are laid out differently fall into separate
+    /// `PieceClass`es.
+    pub fn location_type(&self, frame_sides: usize) -> LocationType {
+        match (self, frame_sides) {
+            (_, 0) => LocationType::Interior,
+            (Lattice::Hexagonal, 1 | 2) | (_, 1) => LocationType::Edge,
+            _ => LocationType::Corner,
+        }
+    }
+
+    /// Most joins that `cells` pieces can share on a flat board.
+    pub fn max_joins(&self, cells: usize) -> usize {
+        if cells == 0 {
+            return 0;
+        }
+        let cells_f64 = cells as f64;
+        match self {
+            Lattice::Square => 2 * cells - (2.0 * cells_f64.sqrt()).ceil() as usize,
+            Lattice::Hexagonal => 3 * cells - (12.0 * cells_f64 - 3.0).sqrt().ceil() as usize,
+            Lattice::Triangular => (3 * cells - (6.0 * cells_f64).sqrt().ceil() as usize) / 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lattice;
+    use crate::model::{
+        FastCombinations, LocationType, PuzzleStructure, SearchOption, SearchOrder, SearchProgress,
+    };
+
+    /// Ways to lay the pieces of the board on the first cells of a row scan,
+    /// at each depth, counting every turn that fits a piece's frame sides to
+    /// the cell. Joins are left out, as they do not depend on the classes.
+    fn brute_force_placements(puzzle_structure: &PuzzleStructure) -> Vec<f64> {
+        let search_order = SearchOrder::for_structure(puzzle_structure, SearchOption::ScanRows);
+        let frames: Vec<Vec<bool>> = search_order
+            .order
+            .iter()
+            .map(|location| puzzle_structure.frame(location.x, location.y))
+            .collect();
+        let turns = |piece: &[bool], cell: &[bool]| {
+            (0..cell.len())
+                .filter(|turn| {
+                    (0..cell.len()).all(|side| piece[(side + turn) % cell.len()] == cell[side])
+                })
+                .count() as f64
+        };
+
+        fn place(
+            depth: usize,
+            frames: &[Vec<bool>],
+            used: &mut Vec<bool>,
+            ways: f64,
+            counts: &mut Vec<f64>,
+            turns: &dyn Fn(&[bool], &[bool]) -> f64,
+        ) {
+            counts[depth] += ways;
+            if depth == frames.len() {
+                return;
+            }
+            (0..frames.len()).for_each(|piece| {
+                if used[piece] {
+                    return;
+                }
+                let fits = turns(&frames[piece], &frames[depth]);
+                if fits > 0.0 {
+                    used[piece] = true;
+                    place(depth + 1, frames, used, ways * fits, counts, turns);
+                    used[piece] = false;
+                }
+            });
+        }
+
+        let mut counts: Vec<f64> = vec![0.0; frames.len() + 1];
+        place(
+            0,
+            &frames,
+            &mut vec![false; frames.len()],
+            1.0,
+            &mut counts,
+            &turns,
+        );
+        counts
+    }
+
+    /// Ways to lay the pieces as the combinatorics count them.
+    fn class_placements(puzzle_structure: &PuzzleStructure) -> Vec<f64> {
+        let puzzle_combinations = FastCombinations::new(puzzle_structure);
+        let search_order = SearchOrder::for_structure(puzzle_structure, SearchOption::ScanRows);
+        SearchProgress::new(puzzle_structure, &search_order)
+            .progress
+            .iter()
+            .map(|used| {
+                let log10_ways: f64 = puzzle_combinations
+                    .log10_pieces
+                    .iter()
+                    .zip(used.pieces.iter())
+                    .map(|(log10_pieces, pieces)| log10_pieces[*pieces])
+                    .sum();
+                10f64.powf(log10_ways)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_location_classes() {
+        let boards = [
+            PuzzleStructure::new(3, 3, 1, 1),
+            PuzzleStructure::new(3, 3, 1, 1)
+                .with_lattice(Lattice::Hexagonal)
+                .unwrap(),
+            PuzzleStructure::new(4, 3, 1, 1)
+                .with_lattice(Lattice::Triangular)
+                .unwrap(),
+        ];
+        boards.iter().for_each(|puzzle_structure| {
+            let exact = brute_force_placements(puzzle_structure);
+            let counted = class_placements(puzzle_structure);
+            assert_eq!(exact.len(), counted.len());
+            exact
+                .iter()
+                .zip(counted.iter())
+                .for_each(|(exact, counted)| assert!((exact - counted).abs() <= 1e-6 * exact));
+        });
+
+        // Hexagon corners show three or four sides to the frame, and edges one
+        // or two, so each splits into two classes.
+        let hexagonal = &boards[1];
+        let layouts = |location_type: LocationType| {
+            hexagonal
+                .piece_classes
+                .iter()
+                .filter(|class| class.location_type == location_type)
+                .count()
+        };
+        assert_eq!(layouts(LocationType::Corner), 2);
+        assert_eq!(layouts(LocationType::Edge), 2);
+        assert_eq!(layouts(LocationType::Interior), 1);
+    }
+
+    #[test]
+    fn test_max_joins() {
+        assert_eq!(Lattice::Square.max_joins(9), 12);
+        assert_eq!(Lattice::Hexagonal.max_joins(7), 12);
+        assert_eq!(Lattice::Triangular.max_joins(6), 6);
+        assert_eq!(Lattice::Triangular.max_joins(1), 0);
+    }
+}
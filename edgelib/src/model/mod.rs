@@ -1,13 +1,29 @@
+mod band_order;
+mod board_shape;
+mod combinatorics;
+mod dihedral;
 mod fast_combinations;
+mod lattice;
 mod puzzle_combinations;
 mod puzzle_structure;
 mod search_nodes;
 mod search_order;
 mod search_progress;
+mod search_spread;
+mod search_strategy;
+mod search_summary;
 
+pub use band_order::*;
+pub use board_shape::*;
+pub use combinatorics::*;
+pub use dihedral::*;
 pub use fast_combinations::*;
+pub use lattice::*;
 pub use puzzle_combinations::*;
 pub use puzzle_structure::*;
 pub use search_nodes::*;
 pub use search_order::*;
 pub use search_progress::*;
+pub use search_spread::*;
+pub use search_strategy::*;
+pub use search_summary::*;
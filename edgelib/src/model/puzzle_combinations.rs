@@ -1,19 +1,27 @@
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
 
-use crate::model::PuzzleStructure;
+use crate::instance::{Piece, Placement, PuzzleInstance, solved_sides};
+use crate::model::{
+    Lattice, Orientations, PieceClass, PuzzleStructure, SearchOrder, SearchProgress, Used,
+};
+use crate::solver::BacktrackSolver;
+
+/// Most solver nodes `log10_exact_moment_ratios` spends over every colouring
+/// before giving up on a board.
+pub const MAX_MOMENT_NODES: u64 = 50_000_000;
 
 pub struct PuzzleCombinations {
     pub middle_probablity: Vec<(BigUint, BigUint)>,
     pub border_probablity: Vec<(BigUint, BigUint)>,
-    pub corner_combinations: Vec<BigUint>,
-    pub edge_combinations: Vec<BigUint>,
-    pub interior_combinations: Vec<BigUint>,
+    /// Ways to lay `n` pieces of each piece class.
+    pub piece_combinations: Vec<Vec<BigUint>>,
     pub log10_middle: Vec<f64>,
     pub log10_border: Vec<f64>,
-    pub log10_corner: Vec<f64>,
-    pub log10_edge: Vec<f64>,
-    pub log10_interior: Vec<f64>,
+    pub log10_pieces: Vec<Vec<f64>>,
+    pub piece_classes: Vec<PieceClass>,
+    pub orientations: Orientations,
+    pub puzzle_structure: PuzzleStructure,
 }
 
 impl PuzzleCombinations {
@@ -23,10 +31,7 @@ impl PuzzleCombinations {
         let size3: usize = puzzle_structure.corners;
         let size4: usize = puzzle_structure.edges;
         let size5: usize = puzzle_structure.interiors;
-        let max_size: usize = *(vec![size1, size2, size3, size4, size5]
-            .iter()
-            .max()
-            .unwrap());
+        let max_size: usize = *([size1, size2, size3, size4, size5].iter().max().unwrap());
         let f: Vec<BigUint> = generate_factorials(max_size);
         let p: Vec<Vec<BigUint>> = generate_permutions(&f);
         let c: Vec<Vec<BigUint>> = generate_combinations(&f);
@@ -35,35 +40,198 @@ impl PuzzleCombinations {
             middle_joins(&puzzle_structure.middle_join_counts, &p, &c);
         let border_probablity: Vec<(BigUint, BigUint)> =
             border_joins(&puzzle_structure.border_join_counts, &p, &c);
-        let corner_combinations: Vec<BigUint> = p[puzzle_structure.corners].clone();
-        let edge_combinations: Vec<BigUint> = p[puzzle_structure.edges].clone();
-        let interior_combinations: Vec<BigUint> = p[puzzle_structure.interiors]
+        let orientations = puzzle_structure.orientations;
+        let piece_combinations: Vec<Vec<BigUint>> = puzzle_structure
+            .piece_classes
             .iter()
-            .enumerate()
-            .map(|(index, combinations)| {
-                combinations.clone() * BigUint::from(4usize).pow(index as u32)
-            })
+            .map(|class| oriented(&p[class.pieces], orientations.for_frame(&class.frame)))
             .collect();
 
         let log10_middle: Vec<f64> = convert_ratio(&middle_probablity);
         let log10_border: Vec<f64> = convert_ratio(&border_probablity);
-        let log10_corner: Vec<f64> = convert_value(&corner_combinations);
-        let log10_edge: Vec<f64> = convert_value(&edge_combinations);
-        let log10_interior: Vec<f64> = convert_value(&interior_combinations);
+        let log10_pieces: Vec<Vec<f64>> = piece_combinations
+            .iter()
+            .map(|combinations| convert_value(combinations))
+            .collect();
 
         PuzzleCombinations {
             middle_probablity,
             border_probablity,
-            corner_combinations,
-            edge_combinations,
-            interior_combinations,
+            piece_combinations,
             log10_middle,
             log10_border,
-            log10_corner,
-            log10_edge,
-            log10_interior,
+            log10_pieces,
+            piece_classes: puzzle_structure.piece_classes.clone(),
+            orientations,
+            puzzle_structure: puzzle_structure.clone(),
+        }
+    }
+
+    /// log10 of the expected number of partial solutions with these pieces and
+    /// joins, taken from a single exact ratio.
+    pub fn log10_exact_nodes(&self, used: &Used) -> f64 {
+        let (border_valid, border_total) = &self.border_probablity[used.borders];
+        let (middle_valid, middle_total) = &self.middle_probablity[used.middles];
+        let numerator = self.piece_combinations.iter().zip(used.pieces.iter()).fold(
+            border_valid * middle_valid,
+            |numerator, (combinations, pieces)| numerator * &combinations[*pieces],
+        );
+        let denominator = border_total * middle_total;
+        approximate_log10(&numerator) - approximate_log10(&denominator)
+    }
+
+    /// log10 of `E[N^2] / E[N]^2` at each depth of the search, taken over
+    /// every colouring of the solved board with the structure's join counts,
+    /// each as likely as the generator makes it. `E[N^2]` sums the chance
+    /// that both of a pair of partial placements fit over every pair, which
+    /// is the mean over colourings of the square of the placements that fit,
+    /// so solving each colouring gives it exactly. Hints stay in their solved
+    /// places. The work grows with the colourings and their nodes, so boards
+    /// that would take more than `MAX_MOMENT_NODES` nodes are refused.
+    pub fn log10_exact_moment_ratios(
+        &self,
+        search_progress: &SearchProgress,
+    ) -> Result<Vec<f64>, String> {
+        let puzzle_structure = &self.puzzle_structure;
+        if puzzle_structure.lattice != Lattice::Square {
+            return Err("The exact spread only solves square pieces".to_string());
+        }
+        if puzzle_structure.orientations != Orientations::rotating(Lattice::Square) {
+            return Err("The exact spread only solves pieces that turn".to_string());
+        }
+        let border_counts = &puzzle_structure.border_join_counts;
+        let middle_counts = &puzzle_structure.middle_join_counts;
+        if border_counts.iter().sum::<usize>() != puzzle_structure.border_joins
+            || middle_counts.iter().sum::<usize>() != puzzle_structure.middle_joins
+        {
+            return Err(format!(
+                "Join counts must add up to {} border and {} middle joins",
+                puzzle_structure.border_joins, puzzle_structure.middle_joins
+            ));
+        }
+        let colourings = arrangement_count(border_counts) * arrangement_count(middle_counts);
+        if colourings > BigUint::from(MAX_MOMENT_NODES) {
+            return Err(format!(
+                "The exact spread would solve {} colourings, more than {}",
+                colourings, MAX_MOMENT_NODES
+            ));
+        }
+
+        let search_order = SearchOrder {
+            width: puzzle_structure.width,
+            height: puzzle_structure.height,
+            size: search_progress.cells.len(),
+            order: search_progress.cells.clone(),
+        };
+        let mut sums: Vec<(u128, u128)> = vec![(0, 0); search_progress.progress.len()];
+        let mut count: u128 = 0;
+        let mut nodes_left = MAX_MOMENT_NODES;
+        for_each_arrangement(border_counts, 1, &mut |border_colours| {
+            for_each_arrangement(
+                middle_counts,
+                1 + border_counts.len(),
+                &mut |middle_colours| {
+                    let pieces: Vec<Piece> = puzzle_structure
+                        .locations()
+                        .zip(solved_sides(
+                            puzzle_structure,
+                            border_colours.to_vec(),
+                            middle_colours.to_vec(),
+                        ))
+                        .map(|(location, sides)| {
+                            if location.hint {
+                                Piece::fixed(
+                                    sides,
+                                    Placement {
+                                        x: location.x,
+                                        y: location.y,
+                                        rotation: 0,
+                                    },
+                                )
+                            } else {
+                                Piece::new(sides)
+                            }
+                        })
+                        .collect();
+                    let puzzle_instance = PuzzleInstance::new(
+                        puzzle_structure.width,
+                        puzzle_structure.height,
+                        pieces,
+                    );
+                    let result =
+                        BacktrackSolver::new(&puzzle_instance, puzzle_structure, &search_order)
+                            .solve(None, Some(nodes_left));
+                    let nodes: u64 = result.nodes.iter().skip(1).sum();
+                    if !result.complete {
+                        return Err(format!(
+                            "The exact spread would take more than {} solver nodes",
+                            MAX_MOMENT_NODES
+                        ));
+                    }
+                    nodes_left -= nodes;
+                    sums.iter_mut().zip(result.nodes.iter()).for_each(
+                        |((sum, square_sum), nodes)| {
+                            *sum += *nodes as u128;
+                            *square_sum += (*nodes as u128).pow(2);
+                        },
+                    );
+                    count += 1;
+                    Ok(())
+                },
+            )
+        })?;
+
+        Ok(sums
+            .iter()
+            .map(|(sum, square_sum)| {
+                (*square_sum as f64).log10() + (count as f64).log10() - 2.0 * (*sum as f64).log10()
+            })
+            .collect())
+    }
+}
+
+/// Number of distinct orderings of a list with `counts` copies of each colour.
+fn arrangement_count(counts: &[usize]) -> BigUint {
+    let f: Vec<BigUint> = generate_factorials(counts.iter().sum());
+    counts
+        .iter()
+        .fold(f[f.len() - 1].clone(), |arrangements, count| {
+            arrangements / &f[*count]
+        })
+}
+
+/// Calls `action` with every distinct ordering of a list with `counts`
+/// copies of each colour, numbered from `first_colour`, stopping at the
+/// first error.
+fn for_each_arrangement(
+    counts: &[usize],
+    first_colour: usize,
+    action: &mut dyn FnMut(&[usize]) -> Result<(), String>,
+) -> Result<(), String> {
+    fn place(
+        left: &mut [usize],
+        first_colour: usize,
+        arrangement: &mut Vec<usize>,
+        action: &mut dyn FnMut(&[usize]) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if left.iter().all(|count| *count == 0) {
+            return action(arrangement);
+        }
+        for index in 0..left.len() {
+            if left[index] == 0 {
+                continue;
+            }
+            left[index] -= 1;
+            arrangement.push(first_colour + index);
+            let result = place(left, first_colour, arrangement, action);
+            arrangement.pop();
+            left[index] += 1;
+            result?;
         }
+        Ok(())
     }
+
+    place(&mut counts.to_vec(), first_colour, &mut vec![], action)
 }
 
 fn approximate_log10(n: &BigUint) -> f64 {
@@ -97,7 +265,7 @@ fn approximate_log10(n: &BigUint) -> f64 {
 }
 
 fn convert_value(list: &[BigUint]) -> Vec<f64> {
-    list.iter().map(|item| approximate_log10(item)).collect()
+    list.iter().map(approximate_log10).collect()
 }
 
 fn convert_ratio(list_duals: &[(BigUint, BigUint)]) -> Vec<f64> {
@@ -107,10 +275,19 @@ fn convert_ratio(list_duals: &[(BigUint, BigUint)]) -> Vec<f64> {
         .collect()
 }
 
+/// Permutations of pieces that can each be laid `orientations` ways.
+fn oriented(permutations: &[BigUint], orientations: usize) -> Vec<BigUint> {
+    permutations
+        .iter()
+        .enumerate()
+        .map(|(index, combinations)| combinations * BigUint::from(orientations).pow(index as u32))
+        .collect()
+}
+
 fn middle_joins(
-    join_counts: &Vec<usize>,
-    p: &Vec<Vec<BigUint>>,
-    c: &Vec<Vec<BigUint>>,
+    join_counts: &[usize],
+    p: &[Vec<BigUint>],
+    c: &[Vec<BigUint>],
 ) -> Vec<(BigUint, BigUint)> {
     let join_types: usize = join_counts.len();
     let total_joints: usize = join_counts.iter().sum();
@@ -146,9 +323,9 @@ fn middle_joins(
 }
 
 fn border_joins(
-    join_counts: &Vec<usize>,
-    p: &Vec<Vec<BigUint>>,
-    c: &Vec<Vec<BigUint>>,
+    join_counts: &[usize],
+    p: &[Vec<BigUint>],
+    c: &[Vec<BigUint>],
 ) -> Vec<(BigUint, BigUint)> {
     let join_types: usize = join_counts.len();
     let total_joints: usize = join_counts.iter().sum();
@@ -194,7 +371,7 @@ fn generate_factorials(max_value: usize) -> Vec<BigUint> {
     factorial
 }
 
-fn generate_permutions(factorial: &Vec<BigUint>) -> Vec<Vec<BigUint>> {
+fn generate_permutions(factorial: &[BigUint]) -> Vec<Vec<BigUint>> {
     let max_value = factorial.len() - 1;
     (0..=max_value)
         .map(|n| {
@@ -205,7 +382,7 @@ fn generate_permutions(factorial: &Vec<BigUint>) -> Vec<Vec<BigUint>> {
         .collect()
 }
 
-fn generate_combinations(factorial: &Vec<BigUint>) -> Vec<Vec<BigUint>> {
+fn generate_combinations(factorial: &[BigUint]) -> Vec<Vec<BigUint>> {
     let max_value = factorial.len() - 1;
     (0..=max_value)
         .map(|n| {
@@ -218,7 +395,62 @@ fn generate_combinations(factorial: &Vec<BigUint>) -> Vec<Vec<BigUint>> {
 
 #[cfg(test)]
 mod tests {
+    use super::PuzzleCombinations;
+    use crate::model::{
+        FastCombinations, Lattice, NodeCombinatorics, Orientations, PuzzleStructure, Used,
+    };
 
     #[test]
     fn test_border_joins() {}
+
+    #[test]
+    fn test_matches_fast() {
+        let puzzle_structure = PuzzleStructure::new(6, 5, 3, 4);
+        let exact = PuzzleCombinations::new(&puzzle_structure);
+        let fast = FastCombinations::new(&puzzle_structure);
+        let used = Used {
+            corners: 2,
+            edges: 5,
+            interiors: 4,
+            pieces: vec![2, 5, 4],
+            borders: 6,
+            middles: 9,
+        };
+        assert!((exact.log10_nodes(&used) - fast.log10_nodes(&used)).abs() < 1e-9);
+        assert!(
+            (exact.log10_nodes(&used)
+                - (exact.log10_border[6]
+                    + exact.log10_middle[9]
+                    + exact.log10_pieces[0][2]
+                    + exact.log10_pieces[1][5]
+                    + exact.log10_pieces[2][4]))
+                .abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_orientations() {
+        let mut puzzle_structure = PuzzleStructure::new(5, 4, 2, 3);
+        let rotating = FastCombinations::new(&puzzle_structure);
+        puzzle_structure.orientations = Orientations::flippable(Lattice::Square);
+        let flippable = FastCombinations::new(&puzzle_structure);
+        let exact = PuzzleCombinations::new(&puzzle_structure);
+        assert!(
+            (flippable.log10_pieces[2][3] - rotating.log10_pieces[2][3] - 3.0 * 2f64.log10()).abs()
+                < 1e-9
+        );
+        assert!(
+            (flippable.log10_pieces[0][2] - rotating.log10_pieces[0][2] - 2.0 * 2f64.log10()).abs()
+                < 1e-9
+        );
+        assert!((exact.log10_pieces[1][5] - flippable.log10_pieces[1][5]).abs() < 1e-9);
+
+        puzzle_structure.orientations = Orientations::fixed();
+        let fixed = FastCombinations::new(&puzzle_structure);
+        assert!(
+            (rotating.log10_pieces[2][3] - fixed.log10_pieces[2][3] - 3.0 * 4f64.log10()).abs()
+                < 1e-9
+        );
+    }
 }
@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use super::{BoardShape, Lattice, Location};
+
+#[derive(Clone, Debug)]
 pub struct PuzzleStructure {
     pub width: usize,
     pub height: usize,
@@ -8,10 +10,14 @@ pub struct PuzzleStructure {
     pub corners: usize,
     pub edges: usize,
     pub interiors: usize,
+    pub piece_classes: Vec<PieceClass>,
     pub border_joins: usize,
     pub middle_joins: usize,
     pub border_join_counts: Vec<usize>,
     pub middle_join_counts: Vec<usize>,
+    pub topology: Topology,
+    pub lattice: Lattice,
+    pub orientations: Orientations,
 }
 
 #[derive(Clone, Debug)]
@@ -19,7 +25,12 @@ pub struct GridLocation {
     pub x: usize,
     pub y: usize,
     pub location_type: LocationType,
+    /// Index into `piece_classes` of the pieces that fit the cell.
+    pub piece_class: usize,
     pub joins: Vec<Join>,
+    pub hint: bool,
+    /// False for a hole, which holds no piece and has no joins.
+    pub active: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -29,98 +40,262 @@ pub struct Join {
     pub join_type: JoinType,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub enum LocationType {
     Corner,
     Edge,
     Interior,
 }
 
+/// Pieces that can fill each other's cells: those whose frame sides are laid
+/// out the same way once turned. A location type can hold several, such as
+/// hexagon edges with one and two frame sides.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PieceClass {
+    pub location_type: LocationType,
+    /// Which sides face the frame, turned to the least layout.
+    pub frame: Vec<bool>,
+    /// Pieces of the class that are not hints.
+    pub pieces: usize,
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum JoinType {
     Border,
     Middle,
 }
 
-const COORDINATES: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+/// Which sides of the board wrap round to meet the opposite side.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Topology {
+    /// Flat board framed on every side.
+    #[default]
+    Plane,
+    /// Left and right sides meet, leaving a frame only along the top and bottom.
+    Cylinder,
+    /// Both pairs of sides meet, so there is no frame at all.
+    Torus,
+}
+
+/// Which ways a piece can be laid in a cell, as evenly spaced turns and,
+/// for double-sided pieces, turning it over.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Orientations {
+    pub turns: usize,
+    pub flips: bool,
+}
+
+impl Orientations {
+    /// Pieces that turn in the plane, with the frame fixing border pieces.
+    pub fn rotating(lattice: Lattice) -> Self {
+        Orientations {
+            turns: lattice.sides(),
+            flips: false,
+        }
+    }
+
+    /// Pieces that can only be laid one way up and one way round.
+    pub fn fixed() -> Self {
+        Orientations {
+            turns: 1,
+            flips: false,
+        }
+    }
+
+    /// Double-sided pieces that can also be turned over. Border pieces flip
+    /// about the line through their frame sides.
+    pub fn flippable(lattice: Lattice) -> Self {
+        Orientations {
+            turns: lattice.sides(),
+            flips: true,
+        }
+    }
+
+    /// Rectangular tiles, which fit their cell either way round.
+    pub fn rectangular() -> Self {
+        Orientations {
+            turns: 2,
+            flips: false,
+        }
+    }
+
+    /// Ways a piece with these frame sides can be laid in a cell with the
+    /// same layout, keeping its frame sides on the frame. An interior piece
+    /// can be laid every way, a corridor between two opposite frame sides
+    /// either way round.
+    pub fn for_frame(&self, frame: &[bool]) -> usize {
+        let sides = frame.len();
+        let step = sides / self.turns;
+        let keeps =
+            |map: &dyn Fn(usize) -> usize| (0..sides).all(|side| frame[map(side)] == frame[side]);
+        let turns = (0..self.turns)
+            .filter(|turn| keeps(&|side| (side + turn * step) % sides))
+            .count();
+        let flips = if self.flips {
+            (0..sides)
+                .filter(|axis| keeps(&|side| (axis + sides - side) % sides))
+                .count()
+        } else {
+            0
+        };
+        turns + flips
+    }
+}
+
+pub const COORDINATES: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
 
 impl PuzzleStructure {
     pub fn new(width: usize, height: usize, border_types: usize, middle_types: usize) -> Self {
+        Self::from_shape(
+            &BoardShape::rectangle(width, height),
+            border_types,
+            middle_types,
+        )
+    }
+
+    /// Builds the structure of an irregular board. A cell's type follows from
+    /// how many of its sides face outside the shape: none for an interior,
+    /// one for an edge and more for a corner. A join carries a border colour
+    /// when it is `next_to_frame` on either cell, as it is along the frame of
+    /// a rectangle. Next to a concave corner only one of the two cells sees
+    /// the frame, so the border colour also shows on a side of the other
+    /// piece away from its frame.
+    pub fn from_shape(shape: &BoardShape, border_types: usize, middle_types: usize) -> Self {
+        Self::build(
+            shape,
+            Topology::Plane,
+            Lattice::Square,
+            border_types,
+            middle_types,
+        )
+    }
+
+    /// Same board with its sides wrapped round as the topology says. Cells
+    /// that lose their frame become edges or interiors, the join counts are
+    /// split evenly again and hints are kept. Fails if the wrapped sides are
+    /// too short or cannot pair up their rows.
+    pub fn with_topology(self, topology: Topology) -> Result<Self, String> {
+        let lattice = self.lattice;
+        self.rebuild(topology, lattice)
+    }
+
+    /// Same board cut into pieces of another lattice, with the join counts
+    /// split evenly again, hints kept and pieces that rotate. Fails if the
+    /// board wraps round in a way the lattice cannot.
+    pub fn with_lattice(self, lattice: Lattice) -> Result<Self, String> {
+        let topology = self.topology;
+        self.rebuild(topology, lattice)
+    }
+
+    fn rebuild(self, topology: Topology, lattice: Lattice) -> Result<Self, String> {
+        check_wrap(self.width, self.height, topology, lattice)?;
+        let shape = BoardShape {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .grid
+                .iter()
+                .map(|column| column.iter().map(|location| location.active).collect())
+                .collect(),
+        };
+        let hints: Vec<Location> = self
+            .locations()
+            .filter(|location| location.hint)
+            .map(|location| Location {
+                x: location.x,
+                y: location.y,
+            })
+            .collect();
+        let mut puzzle_structure = Self::build(
+            &shape,
+            topology,
+            lattice,
+            self.border_types,
+            self.middle_types,
+        );
+        puzzle_structure
+            .set_hints(&hints)
+            .expect("Hints are on the same shape");
+        if lattice == self.lattice {
+            puzzle_structure.orientations = self.orientations;
+        }
+        Ok(puzzle_structure)
+    }
+
+    fn build(
+        shape: &BoardShape,
+        topology: Topology,
+        lattice: Lattice,
+        border_types: usize,
+        middle_types: usize,
+    ) -> Self {
+        let width = shape.width;
+        let height = shape.height;
         if width <= 1 || height <= 1 {
             panic!("Width and height must be greater than 1");
         }
+        check_wrap(width, height, topology, lattice).unwrap_or_else(|error| panic!("{}", error));
 
         if border_types == 0 || middle_types == 0 {
             panic!("There must be at least one border and middle join type");
         }
 
+        let inside = |x: usize, y: usize, direction: usize| {
+            neighbour(width, height, topology, lattice, x, y, direction)
+                .filter(|(x, y)| shape.cells[*x][*y])
+        };
+        let sides = lattice.sides();
         let grid: Vec<Vec<GridLocation>> = (0..width)
-            .into_iter()
             .map(|x| {
                 (0..height)
-                    .into_iter()
                     .map(|y| {
+                        let active = shape.cells[x][y];
                         let mut joins: Vec<Join> = vec![];
-                        COORDINATES.iter().for_each(|coordinate| {
-                            let test_x: isize = (x as isize) + coordinate.0;
-                            let test_y: isize = (y as isize) + coordinate.1;
-                            if test_x < 0
-                                || test_x >= width as isize
-                                || test_y < 0
-                                || test_y >= height as isize
-                            {
+                        (0..sides).for_each(|direction| {
+                            if !active {
                                 return;
                             }
-                            let mut new_join: Join = Join {
-                                x: test_x as usize,
-                                y: test_y as usize,
-                                join_type: JoinType::Middle,
+                            let Some((test_x, test_y)) = inside(x, y, direction) else {
+                                return;
                             };
-                            if new_join.x == 0 || new_join.x == (width - 1) {
-                                if new_join.y == 0 || new_join.y == (height - 1) || new_join.x == x
-                                {
-                                    new_join.join_type = JoinType::Border;
-                                }
-                            } else if new_join.y == 0 || new_join.y == (height - 1) {
-                                if new_join.y == y {
-                                    new_join.join_type = JoinType::Border;
-                                }
-                            }
-                            joins.push(new_join);
+                            let back = (0..sides)
+                                .find(|side| inside(test_x, test_y, *side) == Some((x, y)))
+                                .expect("Neighbour does not join back");
+                            let border = [(x, y, direction), (test_x, test_y, back)].iter().any(
+                                |(x, y, side)| {
+                                    let frame: Vec<bool> = (0..sides)
+                                        .map(|side| inside(*x, *y, side).is_none())
+                                        .collect();
+                                    next_to_frame(&frame, *side)
+                                },
+                            );
+                            joins.push(Join {
+                                x: test_x,
+                                y: test_y,
+                                join_type: if border {
+                                    JoinType::Border
+                                } else {
+                                    JoinType::Middle
+                                },
+                            });
                         });
 
-                        let location_type: LocationType = match joins.len() {
-                            2 => LocationType::Corner,
-                            3 => LocationType::Edge,
-                            _ => LocationType::Interior,
-                        };
+                        let location_type = lattice.location_type(sides - joins.len());
 
                         GridLocation {
                             x,
                             y,
                             location_type,
+                            piece_class: 0,
                             joins,
+                            hint: false,
+                            active,
                         }
                     })
                     .collect()
             })
             .collect();
 
-        let corners = grid
-            .iter()
-            .flatten()
-            .filter(|location| location.location_type == LocationType::Corner)
-            .count();
-        let edges = grid
-            .iter()
-            .flatten()
-            .filter(|location| location.location_type == LocationType::Edge)
-            .count();
-        let interiors = grid
-            .iter()
-            .flatten()
-            .filter(|location| location.location_type == LocationType::Interior)
-            .count();
         let border_joins = grid
             .iter()
             .flatten()
@@ -147,32 +322,266 @@ impl PuzzleStructure {
             / 2;
 
         let border_join_counts: Vec<usize> = (0..border_types)
-            .map(|border_index| ((border_joins + border_types - border_index - 1) / border_types))
+            .map(|border_index| (border_joins + border_types - border_index - 1) / border_types)
             .collect();
         let middle_join_counts: Vec<usize> = (0..middle_types)
-            .map(|middle_index| ((middle_joins + middle_types - middle_index - 1) / middle_types))
+            .map(|middle_index| (middle_joins + middle_types - middle_index - 1) / middle_types)
             .collect();
 
-        PuzzleStructure {
+        let mut puzzle_structure = PuzzleStructure {
             width,
             height,
             border_types,
             middle_types,
             grid,
-            corners,
-            edges,
-            interiors,
+            corners: 0,
+            edges: 0,
+            interiors: 0,
+            piece_classes: vec![],
             border_joins,
             middle_joins,
             border_join_counts,
             middle_join_counts,
+            topology,
+            lattice,
+            orientations: Orientations::rotating(lattice),
+        };
+        puzzle_structure.classify();
+        puzzle_structure
+    }
+
+    /// Sorts the cells into piece classes by location type and frame layout,
+    /// corners first and interiors last.
+    fn classify(&mut self) {
+        let mut piece_classes: Vec<PieceClass> = vec![];
+        let mut cells: Vec<(usize, usize, usize)> = vec![];
+        [
+            LocationType::Corner,
+            LocationType::Edge,
+            LocationType::Interior,
+        ]
+        .into_iter()
+        .for_each(|location_type| {
+            self.locations()
+                .filter(|location| location.location_type == location_type)
+                .for_each(|location| {
+                    let frame = least_turn(&self.frame(location.x, location.y));
+                    let index = piece_classes
+                        .iter()
+                        .position(|class| {
+                            class.location_type == location_type && class.frame == frame
+                        })
+                        .unwrap_or_else(|| {
+                            piece_classes.push(PieceClass {
+                                location_type: location_type.clone(),
+                                frame,
+                                pieces: 0,
+                            });
+                            piece_classes.len() - 1
+                        });
+                    cells.push((location.x, location.y, index));
+                });
+        });
+        cells
+            .into_iter()
+            .for_each(|(x, y, index)| self.grid[x][y].piece_class = index);
+        self.piece_classes = piece_classes;
+        self.count_pieces();
+    }
+
+    fn count_pieces(&mut self) {
+        self.corners = free_pieces(&self.grid, LocationType::Corner);
+        self.edges = free_pieces(&self.grid, LocationType::Edge);
+        self.interiors = free_pieces(&self.grid, LocationType::Interior);
+        self.piece_classes
+            .iter_mut()
+            .for_each(|class| class.pieces = 0);
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|location| location.active && !location.hint)
+            .for_each(|location| self.piece_classes[location.piece_class].pieces += 1);
+    }
+
+    /// Marks cells whose piece is placed before the search starts. Hinted
+    /// pieces are no longer counted in `corners`, `edges`, `interiors` or
+    /// their piece class, and joins next to them are known once the
+    /// neighbouring cell is filled.
+    /// Fails, leaving the hints as they were, if a hint is not on the board.
+    pub fn set_hints(&mut self, hints: &[Location]) -> Result<(), String> {
+        if let Some(hint) = hints.iter().find(|hint| !self.contains(hint.x, hint.y)) {
+            return Err(format!(
+                "Hint ({}, {}) is outside the puzzle",
+                hint.x, hint.y
+            ));
         }
+        self.grid
+            .iter_mut()
+            .flatten()
+            .for_each(|location| location.hint = false);
+        hints
+            .iter()
+            .for_each(|hint| self.grid[hint.x][hint.y].hint = true);
+
+        self.count_pieces();
+        Ok(())
+    }
+
+    pub fn hints(&self) -> usize {
+        self.locations().filter(|location| location.hint).count()
+    }
+
+    /// Every cell that holds a piece, skipping holes in the shape.
+    pub fn locations(&self) -> impl Iterator<Item = &GridLocation> {
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|location| location.active)
+    }
+
+    /// Number of cells that hold a piece.
+    pub fn cells(&self) -> usize {
+        self.locations().count()
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.grid[x][y].active
+    }
+
+    /// True if every cell of the bounding rectangle holds a piece.
+    pub fn is_rectangle(&self) -> bool {
+        self.cells() == self.width * self.height
+    }
+
+    /// Which sides of a cell face outside the puzzle, by direction.
+    pub fn frame(&self, x: usize, y: usize) -> Vec<bool> {
+        (0..self.lattice.sides())
+            .map(|direction| self.neighbour(x, y, direction).is_none())
+            .collect()
+    }
+
+    /// Number of sides of cells that carry a border colour but are not
+    /// `next_to_frame` on their own cell, one for each border join by a
+    /// concave corner of the shape.
+    pub fn inner_border_sides(&self) -> usize {
+        self.locations()
+            .map(|location| {
+                let frame = self.frame(location.x, location.y);
+                (0..frame.len())
+                    .filter(|direction| {
+                        let Some((x, y)) = self.neighbour(location.x, location.y, *direction)
+                        else {
+                            return false;
+                        };
+                        location.joins.iter().any(|join| {
+                            join.x == x && join.y == y && join.join_type == JoinType::Border
+                        }) && !next_to_frame(&frame, *direction)
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Cell on the other side of `direction` (an index into the lattice's
+    /// offsets, `COORDINATES` for squares), if it is part of the puzzle.
+    pub fn neighbour(&self, x: usize, y: usize, direction: usize) -> Option<(usize, usize)> {
+        neighbour(
+            self.width,
+            self.height,
+            self.topology,
+            self.lattice,
+            x,
+            y,
+            direction,
+        )
+        .filter(|(x, y)| self.contains(*x, *y))
     }
 }
 
+/// True if `side` of a cell or piece runs next to one of its frame sides,
+/// given which of its sides face the frame. This is what makes a side carry a
+/// border colour, for cells of a structure and sides of a piece alike.
+pub fn next_to_frame(frame: &[bool], side: usize) -> bool {
+    let count = frame.len();
+    frame[(side + 1) % count] || frame[(side + count - 1) % count]
+}
+
+/// Frame sides turned so that they read least, which is the same for every
+/// turn of the layout.
+fn least_turn(frame: &[bool]) -> Vec<bool> {
+    (0..frame.len())
+        .map(|turn| {
+            (0..frame.len())
+                .map(|side| frame[(side + turn) % frame.len()])
+                .collect::<Vec<bool>>()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn free_pieces(grid: &[Vec<GridLocation>], location_type: LocationType) -> usize {
+    grid.iter()
+        .flatten()
+        .filter(|location| {
+            location.active && !location.hint && location.location_type == location_type
+        })
+        .count()
+}
+
+/// Checks that sides which wrap round are long enough and, as rows of
+/// hexagons and triangles alternate, that their rows pair up.
+fn check_wrap(
+    width: usize,
+    height: usize,
+    topology: Topology,
+    lattice: Lattice,
+) -> Result<(), String> {
+    if (topology != Topology::Plane && width < 3) || (topology == Topology::Torus && height < 3) {
+        return Err("Sides that wrap round must be at least 3 cells long".to_string());
+    }
+    if (lattice == Lattice::Triangular && topology != Topology::Plane && !width.is_multiple_of(2))
+        || (lattice != Lattice::Square && topology == Topology::Torus && !height.is_multiple_of(2))
+    {
+        return Err(
+            "Sides that wrap round must hold an even number of hexagon or triangle rows"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn neighbour(
+    width: usize,
+    height: usize,
+    topology: Topology,
+    lattice: Lattice,
+    x: usize,
+    y: usize,
+    direction: usize,
+) -> Option<(usize, usize)> {
+    let coordinate = lattice.offsets(x, y)[direction];
+    let test_x = step(x, coordinate.0, width, topology != Topology::Plane)?;
+    let test_y = step(y, coordinate.1, height, topology == Topology::Torus)?;
+    Some((test_x, test_y))
+}
+
+fn step(value: usize, change: isize, size: usize, wrap: bool) -> Option<usize> {
+    let next: isize = (value as isize) + change;
+    if wrap {
+        return Some(next.rem_euclid(size as isize) as usize);
+    }
+    if next < 0 || next >= size as isize {
+        return None;
+    }
+    Some(next as usize)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::PuzzleStructure;
+    use super::{JoinType, LocationType, Orientations, PuzzleStructure, Topology};
+    use crate::model::BoardShape;
+    use crate::model::Lattice;
+    use crate::model::Location;
 
     #[test]
     fn test_build_structure() {
@@ -180,4 +589,130 @@ mod tests {
 
         println!("{:?}", puzzle_structure);
     }
+
+    #[test]
+    fn test_shape_with_hole() {
+        let shape = BoardShape::parse("####\n#..#\n####\n").unwrap();
+        let puzzle_structure = PuzzleStructure::from_shape(&shape, 2, 1);
+        assert_eq!(puzzle_structure.cells(), 10);
+        assert!(!puzzle_structure.contains(1, 1));
+        assert_eq!(puzzle_structure.neighbour(1, 0, 1), None);
+        // Each cell of a ring one cell wide has two sides facing outside.
+        assert_eq!(puzzle_structure.corners, 10);
+        assert_eq!(puzzle_structure.edges, 0);
+        assert_eq!(puzzle_structure.interiors, 0);
+        assert_eq!(puzzle_structure.border_joins, 10);
+        assert_eq!(puzzle_structure.middle_joins, 0);
+
+        let cross = BoardShape::parse(".#.\n###\n.#.\n").unwrap();
+        let puzzle_structure = PuzzleStructure::from_shape(&cross, 1, 1);
+        assert_eq!(
+            puzzle_structure.grid[1][1].location_type,
+            LocationType::Interior
+        );
+        assert_eq!(
+            puzzle_structure.grid[0][1].location_type,
+            LocationType::Corner
+        );
+        assert!(
+            puzzle_structure.grid[1][1]
+                .joins
+                .iter()
+                .all(|join| join.join_type == JoinType::Border)
+        );
+    }
+
+    #[test]
+    fn test_piece_classes() {
+        // A ring one cell wide has corners turning round the hole and cells
+        // running straight between frame sides on either side.
+        let shape = BoardShape::parse("####\n#..#\n####\n").unwrap();
+        let puzzle_structure = PuzzleStructure::from_shape(&shape, 2, 1);
+        let classes = &puzzle_structure.piece_classes;
+        assert_eq!(classes.len(), 2);
+        let turning = &classes[puzzle_structure.grid[0][0].piece_class];
+        let straight = &classes[puzzle_structure.grid[1][0].piece_class];
+        assert_eq!((turning.pieces, straight.pieces), (4, 6));
+
+        let rotating = Orientations::rotating(Lattice::Square);
+        let flippable = Orientations::flippable(Lattice::Square);
+        assert_eq!(rotating.for_frame(&turning.frame), 1);
+        assert_eq!(rotating.for_frame(&straight.frame), 2);
+        assert_eq!(flippable.for_frame(&straight.frame), 4);
+        assert_eq!(flippable.for_frame(&[false; 4]), 8);
+        assert_eq!(Orientations::rectangular().for_frame(&[false; 4]), 2);
+
+        let mut hinted = PuzzleStructure::from_shape(&shape, 2, 1);
+        hinted.set_hints(&[Location { x: 1, y: 0 }]).unwrap();
+        assert_eq!(
+            hinted.piece_classes[hinted.grid[1][0].piece_class].pieces,
+            5
+        );
+    }
+
+    #[test]
+    fn test_wrapped_topologies() {
+        let cylinder = PuzzleStructure::new(4, 3, 2, 2)
+            .with_topology(Topology::Cylinder)
+            .unwrap();
+        assert_eq!(cylinder.neighbour(0, 1, 2), Some((3, 1)));
+        assert_eq!(cylinder.neighbour(0, 0, 3), None);
+        assert_eq!(cylinder.corners, 0);
+        assert_eq!(cylinder.edges, 8);
+        assert_eq!(cylinder.interiors, 4);
+        assert_eq!(cylinder.border_joins, 8);
+        assert_eq!(cylinder.middle_joins, 12);
+
+        let mut plane = PuzzleStructure::new(4, 3, 2, 2);
+        plane.set_hints(&[Location { x: 1, y: 1 }]).unwrap();
+        assert!(plane.set_hints(&[Location { x: 9, y: 9 }]).is_err());
+        assert_eq!(plane.hints(), 1);
+        let torus = plane.with_topology(Topology::Torus).unwrap();
+        assert_eq!(torus.neighbour(0, 0, 3), Some((0, 2)));
+        assert_eq!(torus.interiors, 11);
+        assert_eq!(torus.hints(), 1);
+        assert_eq!(torus.border_joins, 0);
+        assert_eq!(torus.middle_joins, 24);
+        assert!(torus.locations().all(|location| location.joins.len() == 4));
+    }
+
+    #[test]
+    fn test_other_lattices() {
+        let hexagonal = PuzzleStructure::new(4, 4, 2, 2)
+            .with_lattice(Lattice::Hexagonal)
+            .unwrap();
+        assert_eq!(hexagonal.neighbour(1, 0, 2), Some((0, 1)));
+        assert_eq!(hexagonal.neighbour(1, 1, 1), Some((2, 2)));
+        assert_eq!(hexagonal.corners, 6);
+        assert_eq!(hexagonal.edges, 6);
+        assert_eq!(hexagonal.interiors, 4);
+        assert_eq!(hexagonal.border_joins + hexagonal.middle_joins, 33);
+
+        let triangular = PuzzleStructure::new(6, 3, 2, 2)
+            .with_lattice(Lattice::Triangular)
+            .unwrap();
+        assert_eq!(triangular.neighbour(0, 0, 1), Some((0, 1)));
+        assert_eq!(triangular.neighbour(1, 0, 2), None);
+        assert_eq!(triangular.corners, 2);
+        assert_eq!(triangular.edges, 8);
+        assert_eq!(triangular.interiors, 8);
+        assert_eq!(triangular.border_joins, 14);
+        assert_eq!(triangular.middle_joins, 7);
+
+        let torus = PuzzleStructure::new(6, 4, 2, 2)
+            .with_lattice(Lattice::Triangular)
+            .unwrap()
+            .with_topology(Topology::Torus)
+            .unwrap();
+        assert_eq!(torus.interiors, 24);
+        assert!(torus.locations().all(|location| location.joins.len() == 3));
+
+        // Triangles alternate along a row, so an odd row cannot wrap round.
+        let odd = PuzzleStructure::new(5, 4, 2, 2)
+            .with_lattice(Lattice::Triangular)
+            .unwrap();
+        assert!(odd.with_topology(Topology::Cylinder).is_err());
+        let short = PuzzleStructure::new(2, 4, 2, 2);
+        assert!(short.with_topology(Topology::Cylinder).is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use super::{FastCombinations, PuzzleCombinations, SearchProgress, Used};
+use super::{Combinatorics, add_log10s};
 
 #[derive(Clone, Debug)]
 pub struct SearchNodes {
@@ -6,22 +6,24 @@ pub struct SearchNodes {
 }
 
 impl SearchNodes {
-    pub fn new(puzzle_combinations: &FastCombinations, search_progress: &SearchProgress) -> Self {
-        if search_progress.width <= 1 || search_progress.height <= 1 {
-            panic!("Width and height must be greater than 1");
+    /// Profile of a search from any model of a puzzle, one depth per cell
+    /// or cube placed.
+    pub fn new<C: Combinatorics + ?Sized>(
+        puzzle_combinations: &C,
+        search_progress: &C::Progress,
+    ) -> Self {
+        SearchNodes {
+            nodes: puzzle_combinations.log10_depths(search_progress),
         }
-        let nodes: Vec<f64> = search_progress
-            .progress
-            .iter()
-            .map(|item: &Used| {
-                puzzle_combinations.log10_border[item.borders]
-                    + puzzle_combinations.log10_middle[item.middles]
-                    + puzzle_combinations.log10_corner[item.corners]
-                    + puzzle_combinations.log10_edge[item.edges]
-                    + puzzle_combinations.log10_interior[item.interiors]
-            })
-            .collect();
+    }
+
+    /// log10 of the expected nodes summed over every depth.
+    pub fn log10_total(&self) -> f64 {
+        add_log10s(&self.nodes)
+    }
 
-        SearchNodes { nodes }
+    /// log10 of the expected nodes at the busiest depth.
+    pub fn log10_peak(&self) -> f64 {
+        self.nodes.iter().copied().fold(f64::NEG_INFINITY, f64::max)
     }
 }
@@ -1,3 +1,7 @@
+use serde::Deserialize;
+
+use super::{PuzzleStructure, SearchOrderStrategy};
+
 pub struct SearchOrder {
     pub width: usize,
     pub height: usize,
@@ -5,12 +9,13 @@ pub struct SearchOrder {
     pub order: Vec<Location>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Location {
     pub x: usize,
     pub y: usize,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SearchOption {
     ScanRows,
     ScanColumns,
@@ -24,6 +29,40 @@ pub enum SearchOption {
     FrameFirstColumns,
     FrameLastRows,
     FrameLastColumns,
+    /// Each anti-diagonal `x + y` in turn, from the top left corner.
+    AntiDiagonal,
+    /// Generalised Hilbert curve, which steps between neighbours on any
+    /// rectangle, save one diagonal step when the longer side is odd and the
+    /// shorter side even.
+    Hilbert,
+    /// Z-order curve, by the interleaved bits of `x` and `y`.
+    Morton,
+    /// Peano curve of the smallest power of 3 square that covers the board,
+    /// keeping the cells on the board.
+    Peano,
+}
+
+impl SearchOption {
+    pub fn all() -> Vec<SearchOption> {
+        vec![
+            SearchOption::ScanRows,
+            SearchOption::ScanColumns,
+            SearchOption::ScanLargest,
+            SearchOption::BuildSquare,
+            SearchOption::SpiralIn,
+            SearchOption::SpiralInSquare,
+            SearchOption::SpiralOut,
+            SearchOption::SpiralOutSquare,
+            SearchOption::FrameFirstRows,
+            SearchOption::FrameFirstColumns,
+            SearchOption::FrameLastRows,
+            SearchOption::FrameLastColumns,
+            SearchOption::AntiDiagonal,
+            SearchOption::Hilbert,
+            SearchOption::Morton,
+            SearchOption::Peano,
+        ]
+    }
 }
 
 impl SearchOrder {
@@ -47,6 +86,10 @@ impl SearchOrder {
             SearchOption::FrameFirstRows => frame_rows(width, height, false, false),
             SearchOption::FrameLastColumns => frame_columns(width, height, false, true),
             SearchOption::FrameLastRows => frame_rows(width, height, false, true),
+            SearchOption::AntiDiagonal => anti_diagonal(width, height),
+            SearchOption::Hilbert => hilbert(width, height),
+            SearchOption::Morton => morton(width, height),
+            SearchOption::Peano => peano(width, height),
         };
 
         SearchOrder {
@@ -56,6 +99,174 @@ impl SearchOrder {
             order,
         }
     }
+
+    /// Built-in order for the structure's shape, skipping any holes. Orders
+    /// walk the `x`, `y` grid, where hexagon and triangle rows are still runs
+    /// of neighbours, so row scans and spirals carry over to every lattice.
+    pub fn for_structure(puzzle_structure: &PuzzleStructure, search_option: SearchOption) -> Self {
+        Self::from_strategy(puzzle_structure, &search_option)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Order a strategy gives for the structure's shape, skipping any holes.
+    /// Fails if the strategy does not visit every cell exactly once.
+    pub fn from_strategy(
+        puzzle_structure: &PuzzleStructure,
+        strategy: &dyn SearchOrderStrategy,
+    ) -> Result<Self, String> {
+        let order: Vec<Location> = strategy
+            .order(puzzle_structure.width, puzzle_structure.height)
+            .into_iter()
+            .filter(|location| {
+                location.x >= puzzle_structure.width
+                    || location.y >= puzzle_structure.height
+                    || puzzle_structure.contains(location.x, location.y)
+            })
+            .collect();
+        Self::from_structure_locations(puzzle_structure, order)
+            .map_err(|error| format!("Search order {} is not valid: {}", strategy.name(), error))
+    }
+
+    /// Builds an order from an explicit list of cells, which must visit every
+    /// cell of the grid exactly once.
+    pub fn from_locations(
+        width: usize,
+        height: usize,
+        order: Vec<Location>,
+    ) -> Result<Self, String> {
+        if width <= 1 || height <= 1 {
+            return Err("Width and height must be greater than 1".to_string());
+        }
+        check_locations(width, height, width * height, |_, _| true, order)
+    }
+
+    /// Builds an order from an explicit list of cells, which must visit every
+    /// cell of the structure's shape exactly once.
+    pub fn from_structure_locations(
+        puzzle_structure: &PuzzleStructure,
+        order: Vec<Location>,
+    ) -> Result<Self, String> {
+        check_locations(
+            puzzle_structure.width,
+            puzzle_structure.height,
+            puzzle_structure.cells(),
+            |x, y| puzzle_structure.contains(x, y),
+            order,
+        )
+    }
+
+    /// Reads an order written as one `x,y` pair per line. A header line and
+    /// blank lines are skipped.
+    pub fn parse_csv(width: usize, height: usize, text: &str) -> Result<Self, String> {
+        Self::from_locations(width, height, parse_locations(text)?)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut text = String::from("x,y\n");
+        self.order.iter().for_each(|location| {
+            text.push_str(&format!("{},{}\n", location.x, location.y));
+        });
+        text
+    }
+
+    pub fn parse_json(width: usize, height: usize, text: &str) -> Result<Self, String> {
+        Self::from_locations(width, height, parse_json_locations(text)?)
+    }
+
+    /// Writes the order as a JSON list of `[x, y]` pairs.
+    pub fn to_json(&self) -> String {
+        let cells: Vec<[usize; 2]> = self
+            .order
+            .iter()
+            .map(|location| [location.x, location.y])
+            .collect();
+        serde_json::to_string_pretty(&cells).expect("Unable to write order as JSON")
+    }
+}
+
+/// Reads a JSON list of cells, each an `[x, y]` pair or an `{"x": .., "y": ..}`
+/// object.
+pub fn parse_json_locations(text: &str) -> Result<Vec<Location>, String> {
+    serde_json::from_str::<Vec<OrderCell>>(text)
+        .map_err(|error| error.to_string())
+        .map(|cells| {
+            cells
+                .into_iter()
+                .map(|cell| match cell {
+                    OrderCell::Pair([x, y]) | OrderCell::Named { x, y } => Location { x, y },
+                })
+                .collect()
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OrderCell {
+    Pair([usize; 2]),
+    Named { x: usize, y: usize },
+}
+
+/// Reads one `x,y` pair per line, skipping a header line and blank lines.
+pub fn parse_locations(text: &str) -> Result<Vec<Location>, String> {
+    let mut order: Vec<Location> = vec![];
+    for (line_index, line) in text.lines().enumerate() {
+        let content = line.trim();
+        if content.is_empty() {
+            continue;
+        }
+        let values: Result<Vec<usize>, _> = content
+            .split(',')
+            .map(|value| value.trim().parse::<usize>())
+            .collect();
+        match values {
+            Ok(values) if values.len() == 2 => order.push(Location {
+                x: values[0],
+                y: values[1],
+            }),
+            Err(_) if line_index == 0 => continue,
+            _ => return Err(format!("Line {} should be x,y", line_index + 1)),
+        }
+    }
+    Ok(order)
+}
+
+fn check_locations(
+    width: usize,
+    height: usize,
+    size: usize,
+    contains: impl Fn(usize, usize) -> bool,
+    order: Vec<Location>,
+) -> Result<SearchOrder, String> {
+    let mut seen: Vec<Vec<bool>> = vec![vec![false; height]; width];
+    for location in order.iter() {
+        if location.x >= width || location.y >= height || !contains(location.x, location.y) {
+            return Err(format!(
+                "Cell ({}, {}) is outside the {}x{} puzzle",
+                location.x, location.y, width, height
+            ));
+        }
+        if seen[location.x][location.y] {
+            return Err(format!(
+                "Cell ({}, {}) appears more than once",
+                location.x, location.y
+            ));
+        }
+        seen[location.x][location.y] = true;
+    }
+    if order.len() != size {
+        return Err(format!(
+            "Order has {} cells but the puzzle has {}",
+            order.len(),
+            size
+        ));
+    }
+
+    Ok(SearchOrder {
+        width,
+        height,
+        size,
+        order,
+    })
 }
 
 fn frame_rows(width: usize, height: usize, ignore: bool, reverse: bool) -> Vec<Location> {
@@ -134,6 +345,9 @@ fn build_square(width: usize, height: usize, size: usize, reverse: bool) -> Vec<
     order
 }
 
+/// With `keep_square`, rows are only taken while the unfilled region is at
+/// least as tall as it is wide, and columns while it is at least as wide as it
+/// is tall, so the region shrinks towards a square before it spirals.
 fn spiral(
     width: usize,
     height: usize,
@@ -154,7 +368,7 @@ fn spiral(
 
         match direction {
             0 => {
-                if !keep_square || left_height <= left_height {
+                if !keep_square || left_width <= left_height {
                     (start_x..end_x).for_each(|x| {
                         order.push(Location { x, y: start_y });
                     });
@@ -162,15 +376,15 @@ fn spiral(
                 }
             }
             1 => {
-                if !keep_square || left_width <= left_width {
+                if !keep_square || left_height <= left_width {
                     (start_y..end_y).for_each(|y| {
-                        order.push(Location { x: end_x - 1, y: y });
+                        order.push(Location { x: end_x - 1, y });
                     });
                     end_x -= 1;
                 }
             }
             2 => {
-                if !keep_square || left_height <= left_height {
+                if !keep_square || left_width <= left_height {
                     (start_x..end_x).rev().for_each(|x| {
                         order.push(Location { x, y: end_y - 1 });
                     });
@@ -178,9 +392,9 @@ fn spiral(
                 }
             }
             _ => {
-                if !keep_square || left_width <= left_width {
+                if !keep_square || left_height <= left_width {
                     (start_y..end_y).rev().for_each(|y| {
-                        order.push(Location { x: start_x, y: y });
+                        order.push(Location { x: start_x, y });
                     });
                     start_x += 1;
                 }
@@ -193,3 +407,236 @@ fn spiral(
     }
     order
 }
+
+fn anti_diagonal(width: usize, height: usize) -> Vec<Location> {
+    (0..width + height - 1)
+        .flat_map(|diagonal| {
+            (diagonal.saturating_sub(width - 1)..=diagonal.min(height - 1))
+                .map(move |y| Location { x: diagonal - y, y })
+        })
+        .collect()
+}
+
+/// After Jakub Červený's gilbert2d, which splits the rectangle along its
+/// longer side, keeping the halves even where it can so the curve joins up.
+fn hilbert(width: usize, height: usize) -> Vec<Location> {
+    let mut order: Vec<Location> = vec![];
+    let (width, height) = (width as i64, height as i64);
+    if width >= height {
+        hilbert_part(&mut order, (0, 0), (width, 0), (0, height));
+    } else {
+        hilbert_part(&mut order, (0, 0), (0, height), (width, 0));
+    }
+    order
+}
+
+/// Fills the block at `start` spanned by the major axis `a` and minor axis `b`.
+fn hilbert_part(order: &mut Vec<Location>, start: (i64, i64), a: (i64, i64), b: (i64, i64)) {
+    let (x, y) = start;
+    let along = (a.0 + a.1).abs();
+    let across = (b.0 + b.1).abs();
+    let step_a = (a.0.signum(), a.1.signum());
+    let step_b = (b.0.signum(), b.1.signum());
+
+    if across == 1 || along == 1 {
+        let (count, step) = if across == 1 {
+            (along, step_a)
+        } else {
+            (across, step_b)
+        };
+        (0..count).for_each(|index| {
+            order.push(Location {
+                x: (x + step.0 * index) as usize,
+                y: (y + step.1 * index) as usize,
+            })
+        });
+        return;
+    }
+
+    let mut a2 = (a.0.div_euclid(2), a.1.div_euclid(2));
+    let mut b2 = (b.0.div_euclid(2), b.1.div_euclid(2));
+    if 2 * along > 3 * across {
+        if (a2.0 + a2.1).abs() % 2 == 1 && along > 2 {
+            a2 = (a2.0 + step_a.0, a2.1 + step_a.1);
+        }
+        hilbert_part(order, start, a2, b);
+        hilbert_part(order, (x + a2.0, y + a2.1), (a.0 - a2.0, a.1 - a2.1), b);
+    } else {
+        if (b2.0 + b2.1).abs() % 2 == 1 && across > 2 {
+            b2 = (b2.0 + step_b.0, b2.1 + step_b.1);
+        }
+        hilbert_part(order, start, b2, a2);
+        hilbert_part(order, (x + b2.0, y + b2.1), a, (b.0 - b2.0, b.1 - b2.1));
+        hilbert_part(
+            order,
+            (
+                x + (a.0 - step_a.0) + (b2.0 - step_b.0),
+                y + (a.1 - step_a.1) + (b2.1 - step_b.1),
+            ),
+            (-b2.0, -b2.1),
+            (-(a.0 - a2.0), -(a.1 - a2.1)),
+        );
+    }
+}
+
+fn morton(width: usize, height: usize) -> Vec<Location> {
+    let interleave = |location: &Location| {
+        (0..usize::BITS / 2).fold(0u128, |key, bit| {
+            key | ((((location.x >> bit) & 1) as u128) << (2 * bit))
+                | ((((location.y >> bit) & 1) as u128) << (2 * bit + 1))
+        })
+    };
+    let mut order: Vec<Location> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Location { x, y }))
+        .collect();
+    order.sort_by_key(interleave);
+    order
+}
+
+fn peano(width: usize, height: usize) -> Vec<Location> {
+    let mut size = 1;
+    while size < width.max(height) {
+        size *= 3;
+    }
+    let mut order: Vec<Location> = vec![];
+    peano_part(&mut order, 0, 0, size, false, false);
+    order.retain(|location| location.x < width && location.y < height);
+    order
+}
+
+/// Visits the 3 by 3 blocks of a square in columns that run up and down in
+/// turn, mirroring each block so it starts next to where the last one ended.
+fn peano_part(
+    order: &mut Vec<Location>,
+    x: usize,
+    y: usize,
+    size: usize,
+    flip_x: bool,
+    flip_y: bool,
+) {
+    if size == 1 {
+        order.push(Location { x, y });
+        return;
+    }
+    let third = size / 3;
+    (0..3).for_each(|column| {
+        (0..3).for_each(|step| {
+            let row = if column % 2 == 1 { 2 - step } else { step };
+            let block_x = if flip_x { 2 - column } else { column };
+            let block_y = if flip_y { 2 - row } else { row };
+            peano_part(
+                order,
+                x + block_x * third,
+                y + block_y * third,
+                third,
+                flip_x ^ (row % 2 == 1),
+                flip_y ^ (column % 2 == 1),
+            );
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Location, SearchOption, SearchOrder};
+    use crate::model::{BoardShape, PuzzleStructure};
+
+    #[test]
+    fn test_from_locations() {
+        let search_order = SearchOrder::new(3, 2, SearchOption::SpiralIn);
+        let copy = SearchOrder::parse_csv(3, 2, &search_order.to_csv()).unwrap();
+        assert_eq!(copy.order, search_order.order);
+        let copy = SearchOrder::parse_json(3, 2, &search_order.to_json()).unwrap();
+        assert_eq!(copy.order, search_order.order);
+        let named = r#"[{"x": 0, "y": 0}, [1, 0], {"y": 0, "x": 2}, [2, 1], [1, 1], [0, 1]]"#;
+        assert_eq!(
+            SearchOrder::parse_json(3, 2, named).unwrap().order,
+            search_order.order
+        );
+        assert!(SearchOrder::parse_json(3, 2, "[[0, 0, 1]]").is_err());
+
+        let mut order = search_order.order.clone();
+        order[5] = Location { x: 0, y: 0 };
+        assert!(SearchOrder::from_locations(3, 2, order.clone()).is_err());
+        order[5] = Location { x: 3, y: 0 };
+        assert!(SearchOrder::from_locations(3, 2, order.clone()).is_err());
+        order.pop();
+        assert!(SearchOrder::from_locations(3, 2, order).is_err());
+    }
+
+    #[test]
+    fn test_curves() {
+        [(5, 3), (7, 4), (6, 6), (2, 9)]
+            .into_iter()
+            .for_each(|(width, height)| {
+                SearchOption::all().into_iter().for_each(|search_option| {
+                    let search_order = SearchOrder::new(width, height, search_option);
+                    let copy = SearchOrder::from_locations(width, height, search_order.order);
+                    assert!(copy.is_ok(), "{:?} on {}x{}", search_option, width, height);
+                })
+            });
+
+        let steps = |search_order: SearchOrder| {
+            search_order
+                .order
+                .windows(2)
+                .map(|pair| pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y))
+                .max()
+                .unwrap()
+        };
+        assert_eq!(steps(SearchOrder::new(6, 4, SearchOption::Hilbert)), 1);
+        assert_eq!(steps(SearchOrder::new(10, 7, SearchOption::Hilbert)), 1);
+        assert_eq!(steps(SearchOrder::new(9, 9, SearchOption::Peano)), 1);
+        let diagonal = SearchOrder::new(3, 2, SearchOption::AntiDiagonal);
+        assert_eq!(
+            diagonal.order[1..3],
+            [Location { x: 1, y: 0 }, Location { x: 0, y: 1 }]
+        );
+        let morton = SearchOrder::new(4, 4, SearchOption::Morton);
+        assert_eq!(morton.order[3], Location { x: 1, y: 1 });
+        assert_eq!(morton.order[4], Location { x: 2, y: 0 });
+    }
+
+    #[test]
+    fn test_square_spirals() {
+        [
+            (SearchOption::SpiralIn, SearchOption::SpiralInSquare),
+            (SearchOption::SpiralOut, SearchOption::SpiralOutSquare),
+        ]
+        .into_iter()
+        .for_each(|(spiral, square)| {
+            [(6, 3), (3, 6), (7, 4)]
+                .into_iter()
+                .for_each(|(width, height)| {
+                    assert_ne!(
+                        SearchOrder::new(width, height, spiral).order,
+                        SearchOrder::new(width, height, square).order
+                    );
+                });
+            assert_eq!(
+                SearchOrder::new(5, 5, spiral).order,
+                SearchOrder::new(5, 5, square).order
+            );
+        });
+
+        // A wide board is trimmed from the sides until the rest is square.
+        let square = SearchOrder::new(6, 3, SearchOption::SpiralInSquare);
+        assert_eq!(square.order[..3], [0, 1, 2].map(|y| Location { x: 5, y }));
+        assert_eq!(square.order[3..6], [2, 1, 0].map(|y| Location { x: 0, y }));
+    }
+
+    #[test]
+    fn test_for_structure() {
+        let shape = BoardShape::parse("###\n#..\n").unwrap();
+        let puzzle_structure = PuzzleStructure::from_shape(&shape, 1, 1);
+        let search_order = SearchOrder::for_structure(&puzzle_structure, SearchOption::SpiralIn);
+        assert_eq!(search_order.size, 4);
+        assert!(
+            SearchOrder::from_structure_locations(&puzzle_structure, search_order.order.clone())
+                .is_ok()
+        );
+        let mut order = search_order.order;
+        order[3] = Location { x: 1, y: 1 };
+        assert!(SearchOrder::from_structure_locations(&puzzle_structure, order).is_err());
+    }
+}
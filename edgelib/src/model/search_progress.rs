@@ -7,17 +7,31 @@ pub struct SearchProgress {
     pub size: usize,
     pub end_point: Used,
     pub progress: Vec<Used>,
+    /// Cell filled at each depth after the first, leaving out hints.
+    pub cells: Vec<Location>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Used {
     pub corners: usize,
     pub edges: usize,
     pub interiors: usize,
+    /// Pieces of each of the structure's piece classes.
+    pub pieces: Vec<usize>,
     pub borders: usize,
     pub middles: usize,
 }
 
+impl Used {
+    /// Nothing placed yet, with a count for each piece class of the structure.
+    pub fn empty(puzzle_structure: &PuzzleStructure) -> Self {
+        Used {
+            pieces: vec![0; puzzle_structure.piece_classes.len()],
+            ..Used::default()
+        }
+    }
+}
+
 impl SearchProgress {
     pub fn new(puzzle_structure: &PuzzleStructure, search_order: &SearchOrder) -> Self {
         if puzzle_structure.width <= 1 || puzzle_structure.height <= 1 {
@@ -33,28 +47,64 @@ impl SearchProgress {
             panic!("Search order and Puzzle structure height needs to match");
         }
 
+        let mut filled: Vec<Vec<bool>> = vec![vec![false; search_order.height]; search_order.width];
+        let mut hint_borders: usize = 0;
+        let mut hint_middles: usize = 0;
+        puzzle_structure
+            .grid
+            .iter()
+            .flatten()
+            .filter(|location| location.hint)
+            .for_each(|location| {
+                filled[location.x][location.y] = true;
+                location.joins.iter().for_each(|join| {
+                    if puzzle_structure.grid[join.x][join.y].hint {
+                        match join.join_type {
+                            JoinType::Border => hint_borders += 1,
+                            JoinType::Middle => hint_middles += 1,
+                        }
+                    }
+                });
+            });
+
         let end_point: Used = Used {
             corners: puzzle_structure.corners,
             edges: puzzle_structure.edges,
             interiors: puzzle_structure.interiors,
-            borders: puzzle_structure.border_join_counts.iter().sum(),
-            middles: puzzle_structure.middle_join_counts.iter().sum(),
+            pieces: puzzle_structure
+                .piece_classes
+                .iter()
+                .map(|class| class.pieces)
+                .collect(),
+            borders: puzzle_structure
+                .border_join_counts
+                .iter()
+                .sum::<usize>()
+                .saturating_sub(hint_borders / 2),
+            middles: puzzle_structure
+                .middle_join_counts
+                .iter()
+                .sum::<usize>()
+                .saturating_sub(hint_middles / 2),
         };
 
-        let mut current_used = Used {
-            corners: 0,
-            edges: 0,
-            interiors: 0,
-            borders: 0,
-            middles: 0,
-        };
+        let mut current_used = Used::empty(puzzle_structure);
 
         let mut progress: Vec<Used> = vec![current_used.clone()];
+        let mut cells: Vec<Location> = vec![];
 
-        let mut filled: Vec<Vec<bool>> = vec![vec![false; search_order.height]; search_order.width];
         search_order.order.iter().for_each(|location: &Location| {
-            let mut next_used: Used = current_used.clone();
             let grid_location = &puzzle_structure.grid[location.x][location.y];
+            if !grid_location.active {
+                panic!(
+                    "Search order visits ({}, {}) which is not part of the puzzle",
+                    location.x, location.y
+                );
+            }
+            if grid_location.hint {
+                return;
+            }
+            let mut next_used: Used = current_used.clone();
             grid_location.joins.iter().for_each(|join| {
                 if filled[join.x][join.y] {
                     match join.join_type {
@@ -78,7 +128,9 @@ impl SearchProgress {
                     next_used.interiors += 1;
                 }
             }
+            next_used.pieces[grid_location.piece_class] += 1;
             filled[location.x][location.y] = true;
+            cells.push(location.clone());
             progress.push(next_used.clone());
             current_used = next_used;
         });
@@ -86,9 +138,35 @@ impl SearchProgress {
         SearchProgress {
             width: puzzle_structure.width,
             height: puzzle_structure.height,
-            size: search_order.size,
+            size: progress.len() - 1,
             end_point: end_point.clone(),
             progress,
+            cells,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SearchProgress;
+    use crate::model::{Location, PuzzleStructure, SearchOption, SearchOrder};
+
+    #[test]
+    fn test_hinted_centre() {
+        let mut puzzle_structure = PuzzleStructure::new(3, 3, 2, 2);
+        puzzle_structure
+            .set_hints(&[Location { x: 1, y: 1 }])
+            .unwrap();
+        let search_order = SearchOrder::new(3, 3, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+
+        assert_eq!(search_progress.size, 8);
+        assert_eq!(search_progress.progress.len(), 9);
+        let last = search_progress.progress.last().unwrap();
+        assert_eq!(last.interiors, 0);
+        assert_eq!(last.middles, 4);
+        assert_eq!(last.borders, 8);
+        // The top middle cell already meets the hinted centre.
+        assert_eq!(search_progress.progress[2].middles, 1);
+    }
+}
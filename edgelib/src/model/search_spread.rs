@@ -0,0 +1,207 @@
+use super::{MomentCombinatorics, SearchNodes, SearchProgress};
+
+/// log10 of `E[N^2] / E[N]^2` above which the expected count at a depth
+/// comes mostly from a few puzzles with far more partial solutions than the
+/// rest, so a typical puzzle has fewer.
+pub const RARE_LOG10_RATIO: f64 = 1.0;
+
+/// How far the node counts of random puzzles spread around the expected
+/// counts of `SearchNodes`, from the backend's `log10_moment_ratios`. The
+/// exact backend works out the true ratios by solving every colouring of a
+/// small board. The fast backend's ratios are a mean-field approximation, so
+/// its bounds are a guide to the spread rather than exact.
+#[derive(Clone, Debug)]
+pub struct SearchSpread {
+    /// log10 of `E[N^2] / E[N]^2` at each depth.
+    pub log10_ratio: Vec<f64>,
+}
+
+impl SearchSpread {
+    pub fn new<C: MomentCombinatorics + ?Sized>(
+        puzzle_combinations: &C,
+        search_progress: &SearchProgress,
+    ) -> Result<Self, String> {
+        if search_progress.width <= 1 || search_progress.height <= 1 {
+            panic!("Width and height must be greater than 1");
+        }
+        let log10_ratio: Vec<f64> = puzzle_combinations.log10_moment_ratios(search_progress)?;
+
+        Ok(SearchSpread { log10_ratio })
+    }
+
+    /// log10 of the expected square of the node count at each depth.
+    pub fn log10_second_moment(&self, search_nodes: &SearchNodes) -> Vec<f64> {
+        search_nodes
+            .nodes
+            .iter()
+            .zip(self.log10_ratio.iter())
+            .map(|(nodes, ratio)| 2.0 * nodes + ratio)
+            .collect()
+    }
+
+    /// log10 of the standard deviation of the node count at each depth.
+    pub fn log10_std_dev(&self, search_nodes: &SearchNodes) -> Vec<f64> {
+        search_nodes
+            .nodes
+            .iter()
+            .zip(self.log10_ratio.iter())
+            .map(|(nodes, ratio)| {
+                let excess = 10f64.powf(*ratio) - 1.0;
+                if excess > 0.0 {
+                    nodes + 0.5 * excess.log10()
+                } else {
+                    f64::NEG_INFINITY
+                }
+            })
+            .collect()
+    }
+
+    /// log10 of the expected count one standard deviation either side. A
+    /// lower end at or below zero gives `-inf`.
+    pub fn log10_bounds(&self, search_nodes: &SearchNodes) -> (Vec<f64>, Vec<f64>) {
+        search_nodes
+            .nodes
+            .iter()
+            .zip(self.log10_std_dev(search_nodes))
+            .map(|(nodes, std_dev)| {
+                let mean = 10f64.powf(*nodes);
+                let std_dev = 10f64.powf(std_dev);
+                let lower = mean - std_dev;
+                (
+                    if lower > 0.0 {
+                        lower.log10()
+                    } else {
+                        f64::NEG_INFINITY
+                    },
+                    (mean + std_dev).log10(),
+                )
+            })
+            .unzip()
+    }
+
+    /// True at depths where the expected count is driven by rare puzzles.
+    pub fn rare(&self) -> Vec<bool> {
+        self.log10_ratio
+            .iter()
+            .map(|ratio| *ratio > RARE_LOG10_RATIO)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchSpread;
+    use crate::instance::{Piece, PuzzleInstance, colour_bag, solved_sides};
+    use crate::model::SearchProgress;
+    use crate::model::{
+        FastCombinations, PuzzleCombinations, PuzzleStructure, SearchNodes, SearchOption,
+        SearchOrder,
+    };
+    use crate::solver::BacktrackSolver;
+
+    /// Every distinct ordering of a list of colours.
+    fn arrangements(colours: &[usize]) -> Vec<Vec<usize>> {
+        if colours.is_empty() {
+            return vec![vec![]];
+        }
+        let mut firsts: Vec<usize> = colours.to_vec();
+        firsts.sort_unstable();
+        firsts.dedup();
+        firsts
+            .iter()
+            .flat_map(|first| {
+                let mut rest = colours.to_vec();
+                rest.remove(rest.iter().position(|colour| colour == first).unwrap());
+                arrangements(&rest).into_iter().map(move |mut arrangement| {
+                    arrangement.push(*first);
+                    arrangement
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_variance() {
+        // Solve every colouring of a small board, each equally likely, to get
+        // the true mean and second moment of the nodes at each depth.
+        let puzzle_structure = PuzzleStructure::new(3, 2, 2, 1);
+        let search_order = SearchOrder::new(3, 2, SearchOption::ScanRows);
+        let border_colours = colour_bag(&puzzle_structure.border_join_counts, 1);
+        let middle_colours = colour_bag(&puzzle_structure.middle_join_counts, 3);
+        let colourings = arrangements(&border_colours);
+        let mut sums: Vec<(f64, f64)> = vec![(0.0, 0.0); 7];
+        colourings.iter().for_each(|colouring| {
+            let pieces: Vec<Piece> =
+                solved_sides(&puzzle_structure, colouring.clone(), middle_colours.clone())
+                    .into_iter()
+                    .map(Piece::new)
+                    .collect();
+            let puzzle_instance = PuzzleInstance::new(3, 2, pieces);
+            let mut solver =
+                BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+            solver
+                .solve(None, None)
+                .nodes
+                .iter()
+                .enumerate()
+                .for_each(|(depth, nodes)| {
+                    sums[depth].0 += *nodes as f64;
+                    sums[depth].1 += (*nodes as f64).powi(2);
+                });
+        });
+        let count = colourings.len() as f64;
+
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let exact = PuzzleCombinations::new(&puzzle_structure);
+        let spread = SearchSpread::new(&exact, &search_progress).unwrap();
+        let fast =
+            SearchSpread::new(&FastCombinations::new(&puzzle_structure), &search_progress).unwrap();
+        sums.iter()
+            .zip(spread.log10_ratio.iter().zip(fast.log10_ratio.iter()))
+            .for_each(|((sum, square_sum), (ratio, fast_ratio))| {
+                let mean = sum / count;
+                let true_ratio = (square_sum / count / mean.powi(2)).log10();
+                assert!((ratio - true_ratio).abs() < 1e-9);
+                // The approximation overstates the spread here, by up to a
+                // third of a power of ten.
+                assert!(*fast_ratio >= true_ratio - 1e-9);
+                assert!(fast_ratio - true_ratio < 0.35);
+            });
+
+        let puzzle_structure = PuzzleStructure::new(8, 8, 4, 6);
+        let search_order = SearchOrder::new(8, 8, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let exact = PuzzleCombinations::new(&puzzle_structure);
+        assert!(SearchSpread::new(&exact, &search_progress).is_err());
+    }
+
+    #[test]
+    fn test_spread() {
+        let puzzle_structure = PuzzleStructure::new(4, 4, 1, 1);
+        let search_order = SearchOrder::new(4, 4, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let fast = FastCombinations::new(&puzzle_structure);
+        // With one colour every placement fits, so every puzzle is the same.
+        let spread = SearchSpread::new(&fast, &search_progress).unwrap();
+        assert!(spread.log10_ratio.iter().all(|ratio| ratio.abs() < 1e-9));
+
+        let puzzle_structure = PuzzleStructure::new(5, 5, 3, 6);
+        let fast = FastCombinations::new(&puzzle_structure);
+        let search_order = SearchOrder::new(5, 5, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let search_nodes = SearchNodes::new(&fast, &search_progress);
+        let spread = SearchSpread::new(&fast, &search_progress).unwrap();
+        assert_eq!(spread.log10_ratio[0], 0.0);
+        // Once solutions are rare, the count is mostly zero with the odd
+        // puzzle holding one or a few, so E[N^2] / E[N]^2 is at least 1 / E[N].
+        let last = search_nodes.nodes[25];
+        assert!(last < -1.0);
+        assert!(spread.log10_ratio[25] >= -last);
+        assert!(spread.log10_ratio[25] < 1.0 - last);
+        assert!(spread.rare()[25]);
+        assert!(!spread.rare()[1]);
+        let (lower, upper) = spread.log10_bounds(&search_nodes);
+        assert_eq!(lower[25], f64::NEG_INFINITY);
+        assert!(lower[12] < search_nodes.nodes[12] && search_nodes.nodes[12] < upper[12]);
+    }
+}
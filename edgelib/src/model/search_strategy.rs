@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use super::{BandFamily, Location, SearchOption, SearchOrder};
+
+/// A way of ordering the cells of a board. Implement this to profile or
+/// solve with an order of your own, then add it to a `SearchRegistry` so it
+/// can be picked by name.
+pub trait SearchOrderStrategy: Send + Sync {
+    /// Name the strategy is picked by, in kebab case such as `scan-rows`.
+    fn name(&self) -> String;
+
+    /// Column heading for the strategy's results.
+    fn title(&self) -> String {
+        self.name()
+    }
+
+    /// Every cell of a `width` by `height` grid, each once, in the order to
+    /// fill them. Cells outside a board's shape are dropped afterwards.
+    fn order(&self, width: usize, height: usize) -> Vec<Location>;
+}
+
+impl SearchOrderStrategy for SearchOption {
+    fn name(&self) -> String {
+        let mut name = String::new();
+        format!("{:?}", self).chars().for_each(|letter| {
+            if letter.is_uppercase() && !name.is_empty() {
+                name.push('-');
+            }
+            name.push(letter.to_ascii_lowercase());
+        });
+        name
+    }
+
+    fn title(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn order(&self, width: usize, height: usize) -> Vec<Location> {
+        SearchOrder::new(width, height, *self).order
+    }
+}
+
+/// A set of strategies too large to list, such as every setting of a
+/// parametric order, picked by parsing the name.
+pub trait SearchOrderFamily: Send + Sync {
+    /// Form of the names the family accepts, for help and error messages.
+    fn pattern(&self) -> String;
+
+    fn parse(&self, name: &str) -> Option<Arc<dyn SearchOrderStrategy>>;
+}
+
+/// Search order strategies by name.
+#[derive(Clone, Default)]
+pub struct SearchRegistry {
+    strategies: Vec<Arc<dyn SearchOrderStrategy>>,
+    families: Vec<Arc<dyn SearchOrderFamily>>,
+}
+
+impl SearchRegistry {
+    /// Registry holding the built-in `SearchOption` orders and band orders.
+    pub fn with_builtins() -> Self {
+        let mut registry = SearchRegistry::default();
+        SearchOption::all().into_iter().for_each(|search_option| {
+            registry
+                .register(Arc::new(search_option))
+                .expect("Built-in search names are unique")
+        });
+        registry.register_family(Arc::new(BandFamily));
+        registry
+    }
+
+    /// Adds a strategy, which must not share a name with one already held.
+    pub fn register(&mut self, strategy: Arc<dyn SearchOrderStrategy>) -> Result<(), String> {
+        let name = strategy.name();
+        if self.get(&name).is_some() {
+            return Err(format!(
+                "A search order named {} is already registered",
+                name
+            ));
+        }
+        self.strategies.push(strategy);
+        Ok(())
+    }
+
+    /// Adds a family, which is asked for names no single strategy holds.
+    pub fn register_family(&mut self, family: Arc<dyn SearchOrderFamily>) {
+        self.families.push(family);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn SearchOrderStrategy>> {
+        self.strategies
+            .iter()
+            .find(|strategy| strategy.name() == name)
+            .cloned()
+            .or_else(|| self.families.iter().find_map(|family| family.parse(name)))
+    }
+
+    /// Looks up each name in turn, failing on the first unknown one.
+    pub fn resolve(&self, names: &[String]) -> Result<Vec<Arc<dyn SearchOrderStrategy>>, String> {
+        names
+            .iter()
+            .map(|name| {
+                self.get(name.trim()).ok_or_else(|| {
+                    format!(
+                        "{} is not a known search order, choose from {}",
+                        name,
+                        self.names()
+                            .into_iter()
+                            .chain(self.patterns())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.strategies
+            .iter()
+            .map(|strategy| strategy.name())
+            .collect()
+    }
+
+    pub fn patterns(&self) -> Vec<String> {
+        self.families
+            .iter()
+            .map(|family| family.pattern())
+            .collect()
+    }
+
+    pub fn strategies(&self) -> &[Arc<dyn SearchOrderStrategy>] {
+        &self.strategies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{SearchOrderStrategy, SearchRegistry};
+    use crate::model::{Location, PuzzleStructure, SearchOption, SearchOrder};
+
+    /// Fills the board one column at a time from the right.
+    struct RightToLeft;
+
+    impl SearchOrderStrategy for RightToLeft {
+        fn name(&self) -> String {
+            "right-to-left".to_string()
+        }
+
+        fn order(&self, width: usize, height: usize) -> Vec<Location> {
+            (0..width)
+                .rev()
+                .flat_map(|x| (0..height).map(move |y| Location { x, y }))
+                .collect()
+        }
+    }
+
+    /// Misses the last cell of the board.
+    struct Short;
+
+    impl SearchOrderStrategy for Short {
+        fn name(&self) -> String {
+            "short".to_string()
+        }
+
+        fn order(&self, width: usize, height: usize) -> Vec<Location> {
+            let mut order = SearchOption::ScanRows.order(width, height);
+            order.pop();
+            order
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = SearchRegistry::with_builtins();
+        assert_eq!(registry.names().len(), SearchOption::all().len());
+        assert_eq!(SearchOption::FrameFirstRows.name(), "frame-first-rows");
+        assert_eq!(SearchOption::FrameFirstRows.title(), "FrameFirstRows");
+
+        registry.register(Arc::new(RightToLeft)).unwrap();
+        assert!(registry.register(Arc::new(RightToLeft)).is_err());
+        let strategies = registry
+            .resolve(&["spiral-in".to_string(), "right-to-left".to_string()])
+            .unwrap();
+        assert_eq!(strategies[1].title(), "right-to-left");
+        assert!(registry.resolve(&["spiral-up".to_string()]).is_err());
+        let band = registry.get("band-3-columns-tr-snake").unwrap();
+        assert_eq!(band.name(), "band-3-columns-tr-snake");
+
+        let puzzle_structure = PuzzleStructure::new(4, 3, 1, 2);
+        let search_order =
+            SearchOrder::from_strategy(&puzzle_structure, strategies[0].as_ref()).unwrap();
+        assert_eq!(
+            search_order.order,
+            SearchOrder::for_structure(&puzzle_structure, SearchOption::SpiralIn).order
+        );
+        let search_order =
+            SearchOrder::from_strategy(&puzzle_structure, strategies[1].as_ref()).unwrap();
+        assert_eq!(search_order.order[0], Location { x: 3, y: 0 });
+        assert!(SearchOrder::from_strategy(&puzzle_structure, &Short).is_err());
+    }
+}
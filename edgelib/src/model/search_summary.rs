@@ -0,0 +1,88 @@
+use super::SearchNodes;
+
+/// Headline figures of a search profile, all as log10 values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchSummary {
+    /// Expected nodes summed over every depth.
+    pub log10_total: f64,
+    /// First depth with the most expected nodes.
+    pub peak_depth: usize,
+    pub log10_peak: f64,
+    /// Expected nodes at the final depth, which are the expected solutions.
+    pub log10_solutions: f64,
+    /// Expected children of each node, from each depth to the next.
+    pub log10_branching: Vec<f64>,
+}
+
+impl SearchSummary {
+    pub fn new(search_nodes: &SearchNodes) -> Self {
+        if search_nodes.nodes.is_empty() {
+            panic!("Search nodes must cover at least one depth");
+        }
+
+        let (peak_depth, log10_peak) = search_nodes.nodes.iter().enumerate().fold(
+            (0, search_nodes.nodes[0]),
+            |(peak_depth, log10_peak), (depth, nodes)| {
+                if *nodes > log10_peak {
+                    (depth, *nodes)
+                } else {
+                    (peak_depth, log10_peak)
+                }
+            },
+        );
+        let log10_branching: Vec<f64> = search_nodes
+            .nodes
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+
+        SearchSummary {
+            log10_total: search_nodes.log10_total(),
+            peak_depth,
+            log10_peak,
+            log10_solutions: *search_nodes.nodes.last().unwrap(),
+            log10_branching,
+        }
+    }
+
+    /// Indices of the summaries from the fewest total expected nodes to the
+    /// most, with ties going to the lower peak.
+    pub fn rank(summaries: &[SearchSummary]) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..summaries.len()).collect();
+        ranking.sort_by(|a, b| {
+            summaries[*a]
+                .log10_total
+                .total_cmp(&summaries[*b].log10_total)
+                .then_with(|| {
+                    summaries[*a]
+                        .log10_peak
+                        .total_cmp(&summaries[*b].log10_peak)
+                })
+        });
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchSummary;
+    use crate::model::SearchNodes;
+
+    #[test]
+    fn test_summary() {
+        let summary = SearchSummary::new(&SearchNodes {
+            nodes: vec![0.0, 1.0, 2.0, 2.0, -0.5],
+        });
+        assert_eq!(summary.peak_depth, 2);
+        assert_eq!(summary.log10_peak, 2.0);
+        assert_eq!(summary.log10_solutions, -0.5);
+        assert_eq!(summary.log10_branching, vec![1.0, 1.0, 0.0, -2.5]);
+        let total: f64 = [1.0, 10.0, 100.0, 100.0, 10f64.powf(-0.5)].iter().sum();
+        assert!((summary.log10_total - total.log10()).abs() < 1e-9);
+
+        let flatter = SearchSummary::new(&SearchNodes {
+            nodes: vec![0.0, 1.0, 1.0, 1.0, -0.5],
+        });
+        assert_eq!(SearchSummary::rank(&[summary, flatter]), vec![1, 0]);
+    }
+}
@@ -0,0 +1,825 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::model::{
+    FastCombinations, GridLocation, JoinType, Lattice, Location, LocationType, NodeCombinatorics,
+    PuzzleStructure, Topology, Used, add_log10s,
+};
+
+use super::{OptimisedOrder, OrderOptimiser};
+
+/// Finds an order with the lowest possible total or peak expected nodes.
+///
+/// The expected nodes at each depth depend only on which cells are filled, so
+/// the best order is a shortest path through the sets of filled cells. The
+/// sets are searched best first with a lower bound on the nodes still to come,
+/// which keeps boards up to about 6x6 within reach. The peak objective takes
+/// a second search for the lowest total among orders that keep to the best
+/// peak, as the cheapest way to a set by peak need not lead to that total.
+pub struct ExactOptimiser<'a> {
+    puzzle_structure: &'a PuzzleStructure,
+    puzzle_combinations: &'a FastCombinations,
+    /// Only place cells that share a join with a cell already placed.
+    pub connected: bool,
+    /// Give up once this many sets of filled cells have been reached.
+    pub max_states: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// Sum of the expected nodes over every depth.
+    Total,
+    /// Largest expected nodes at any one depth, with ties broken on the total.
+    Peak,
+}
+
+struct Cell {
+    location: Location,
+    location_type: LocationType,
+    piece_class: usize,
+    /// Free cells sharing a join, as bit indices.
+    joins: Vec<(usize, JoinType)>,
+    hint_borders: usize,
+    hint_middles: usize,
+}
+
+/// Entry in the open list, ordered so the heap pops the lowest key first.
+struct Open {
+    key: (f64, f64),
+    filled: u64,
+    log10_total: f64,
+    log10_peak: f64,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .0
+            .total_cmp(&self.key.0)
+            .then_with(|| other.key.1.total_cmp(&self.key.1))
+            .then_with(|| other.filled.cmp(&self.filled))
+    }
+}
+
+/// Most joins the placed cells can share, by type and in all.
+#[derive(Clone, Default)]
+struct JoinLimit {
+    borders: usize,
+    middles: usize,
+    joins: usize,
+}
+
+struct Reached {
+    log10_total: f64,
+    log10_peak: f64,
+    last: usize,
+}
+
+impl<'a> ExactOptimiser<'a> {
+    pub fn new(
+        puzzle_structure: &'a PuzzleStructure,
+        puzzle_combinations: &'a FastCombinations,
+    ) -> Self {
+        ExactOptimiser {
+            puzzle_structure,
+            puzzle_combinations,
+            connected: false,
+            max_states: 20_000_000,
+        }
+    }
+
+    pub fn optimise(&self, objective: Objective) -> Result<OptimisedOrder, String> {
+        match objective {
+            Objective::Total => self.search(Objective::Total, None),
+            Objective::Peak => {
+                let peak = self.search(Objective::Peak, None)?;
+                self.search(Objective::Total, Some(&peak))
+            }
+        }
+    }
+
+    /// Best first search for the objective. With `within`, only orders whose
+    /// peak is no higher than its peak are searched, starting from its key.
+    fn search(
+        &self,
+        objective: Objective,
+        within: Option<&OptimisedOrder>,
+    ) -> Result<OptimisedOrder, String> {
+        let cells = self.cells();
+        if cells.len() > u64::BITS as usize {
+            return Err(format!(
+                "{} free cells is too many for an exact order, the limit is {}",
+                cells.len(),
+                u64::BITS
+            ));
+        }
+        let hint_any = self.puzzle_structure.hints() > 0;
+        let full: u64 = if cells.len() == u64::BITS as usize {
+            u64::MAX
+        } else {
+            (1u64 << cells.len()) - 1
+        };
+
+        let rest_bounds = self.rest_bounds(cells.len());
+
+        // The best of the heuristic orders, or the order searched within, caps
+        // which sets are worth keeping.
+        let mut optimiser = OrderOptimiser::new(self.puzzle_structure, self.puzzle_combinations);
+        optimiser.connected = self.connected;
+        let upper = match within {
+            Some(within) => self.key(within, objective),
+            None => self.key(&optimiser.finish(optimiser.best_start()), objective),
+        };
+        let peak_limit = within.map_or(f64::INFINITY, |within| {
+            within.search_nodes.log10_peak() + 1e-9
+        });
+
+        let start = Used::empty(self.puzzle_structure);
+        let start_nodes = self.puzzle_combinations.log10_nodes(&start);
+        let mut reached: HashMap<u64, Reached> = HashMap::new();
+        reached.insert(
+            0,
+            Reached {
+                log10_total: start_nodes,
+                log10_peak: start_nodes,
+                last: usize::MAX,
+            },
+        );
+        let mut open: BinaryHeap<Open> = BinaryHeap::new();
+        open.push(Open {
+            key: (start_nodes, start_nodes),
+            filled: 0,
+            log10_total: start_nodes,
+            log10_peak: start_nodes,
+        });
+
+        while let Some(Open {
+            filled,
+            log10_total,
+            log10_peak,
+            ..
+        }) = open.pop()
+        {
+            let state = &reached[&filled];
+            if state.log10_total != log10_total || state.log10_peak != log10_peak {
+                // A cheaper way to this set was found after this entry was pushed.
+                continue;
+            }
+            if filled == full {
+                return Ok(optimiser.finish(self.order(&cells, &reached, full)));
+            }
+
+            let used = self.used(&cells, filled);
+            for (index, cell) in cells.iter().enumerate() {
+                if filled & (1 << index) != 0 {
+                    continue;
+                }
+                let joined = cell
+                    .joins
+                    .iter()
+                    .filter(|(other, _)| filled & (1 << other) != 0);
+                if self.connected
+                    && (filled != 0 || hint_any)
+                    && cell.hint_borders + cell.hint_middles == 0
+                    && joined.clone().next().is_none()
+                {
+                    continue;
+                }
+
+                let mut next_used = used.clone();
+                joined.for_each(|(_, join_type)| match join_type {
+                    JoinType::Border => next_used.borders += 1,
+                    JoinType::Middle => next_used.middles += 1,
+                });
+                next_used.borders += cell.hint_borders;
+                next_used.middles += cell.hint_middles;
+                match cell.location_type {
+                    LocationType::Corner => next_used.corners += 1,
+                    LocationType::Edge => next_used.edges += 1,
+                    LocationType::Interior => next_used.interiors += 1,
+                }
+                next_used.pieces[cell.piece_class] += 1;
+
+                let nodes = self.puzzle_combinations.log10_nodes(&next_used);
+                if nodes > peak_limit {
+                    continue;
+                }
+                let next_total = add_log10s(&[log10_total, nodes]);
+                let next_peak = log10_peak.max(nodes);
+                let next_filled = filled | (1 << index);
+                let better = match reached.get(&next_filled) {
+                    Some(existing) => {
+                        order_key(objective, next_total, next_peak)
+                            < order_key(objective, existing.log10_total, existing.log10_peak)
+                    }
+                    None => true,
+                };
+                if !better {
+                    continue;
+                }
+
+                let next_key =
+                    self.estimate(objective, next_total, next_peak, &next_used, &rest_bounds);
+                let peak_bound = match objective {
+                    Objective::Total => next_key.1,
+                    Objective::Peak => next_key.0,
+                };
+                if next_key.0 > upper.0 + 1e-9 || peak_bound > peak_limit {
+                    continue;
+                }
+                reached.insert(
+                    next_filled,
+                    Reached {
+                        log10_total: next_total,
+                        log10_peak: next_peak,
+                        last: index,
+                    },
+                );
+                if reached.len() > self.max_states {
+                    return Err(format!(
+                        "Stopped after reaching {} sets of filled cells without an answer",
+                        self.max_states
+                    ));
+                }
+                open.push(Open {
+                    key: next_key,
+                    filled: next_filled,
+                    log10_total: next_total,
+                    log10_peak: next_peak,
+                });
+            }
+        }
+
+        Err("No order places every cell".to_string())
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        let free: Vec<&GridLocation> = self
+            .puzzle_structure
+            .locations()
+            .filter(|location| !location.hint)
+            .collect();
+        let mut index: HashMap<(usize, usize), usize> = HashMap::new();
+        free.iter().enumerate().for_each(|(bit, location)| {
+            index.insert((location.x, location.y), bit);
+        });
+
+        free.iter()
+            .map(|location| {
+                let mut cell = Cell {
+                    location: Location {
+                        x: location.x,
+                        y: location.y,
+                    },
+                    location_type: location.location_type.clone(),
+                    piece_class: location.piece_class,
+                    joins: vec![],
+                    hint_borders: 0,
+                    hint_middles: 0,
+                };
+                location
+                    .joins
+                    .iter()
+                    .for_each(|join| match index.get(&(join.x, join.y)) {
+                        Some(bit) => cell.joins.push((*bit, join.join_type.clone())),
+                        None => match join.join_type {
+                            JoinType::Border => cell.hint_borders += 1,
+                            JoinType::Middle => cell.hint_middles += 1,
+                        },
+                    });
+                cell
+            })
+            .collect()
+    }
+
+    fn used(&self, cells: &[Cell], filled: u64) -> Used {
+        let mut used = Used::empty(self.puzzle_structure);
+        cells
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| filled & (1 << index) != 0)
+            .for_each(|(index, cell)| {
+                match cell.location_type {
+                    LocationType::Corner => used.corners += 1,
+                    LocationType::Edge => used.edges += 1,
+                    LocationType::Interior => used.interiors += 1,
+                }
+                used.pieces[cell.piece_class] += 1;
+                used.borders += cell.hint_borders;
+                used.middles += cell.hint_middles;
+                cell.joins
+                    .iter()
+                    .filter(|(other, _)| *other < index && filled & (1 << other) != 0)
+                    .for_each(|(_, join_type)| match join_type {
+                        JoinType::Border => used.borders += 1,
+                        JoinType::Middle => used.middles += 1,
+                    });
+            });
+        used
+    }
+
+    /// Most border joins, middle joins and joins in all that the placed cells
+    /// can share, for each number of free cells placed and corner and edge
+    /// cells among them. On a flat board `t` cells share at most
+    /// `Lattice::max_joins(t)` joins, or half their sides once sides wrap
+    /// round. On a full square rectangle the border joins are limited by how
+    /// the corner and edge cells can sit round the frame ring, and the middle
+    /// joins by how many interior cells there are and how many edge cells
+    /// they could meet.
+    fn join_limits(&self, free: usize) -> Vec<Vec<Vec<JoinLimit>>> {
+        let puzzle_structure = self.puzzle_structure;
+        let hint_cells = puzzle_structure.hints();
+        let hint_count = |location_type: LocationType| {
+            puzzle_structure
+                .locations()
+                .filter(|location| location.hint && location.location_type == location_type)
+                .count()
+        };
+        let hint_corners = hint_count(LocationType::Corner);
+        let hint_edges = hint_count(LocationType::Edge);
+        let (hint_borders, hint_middles) = puzzle_structure
+            .locations()
+            .filter(|location| location.hint)
+            .flat_map(|location| location.joins.iter())
+            .filter(|join| puzzle_structure.grid[join.x][join.y].hint)
+            .fold((0, 0), |(borders, middles), join| match join.join_type {
+                JoinType::Border => (borders + 1, middles),
+                JoinType::Middle => (borders, middles + 1),
+            });
+        let plane = puzzle_structure.topology == Topology::Plane;
+        let lattice = puzzle_structure.lattice;
+        let rectangle = plane && lattice == Lattice::Square && puzzle_structure.is_rectangle();
+        let framed = rectangle && puzzle_structure.width >= 3 && puzzle_structure.height >= 3;
+        let (width, height) = (puzzle_structure.width, puzzle_structure.height);
+        let edge_cells_total = puzzle_structure
+            .locations()
+            .filter(|location| location.location_type == LocationType::Edge)
+            .count();
+        let ring_borders = if rectangle && width >= 2 && height >= 2 {
+            ring_borders([width - 2, height - 2, width - 2, height - 2])
+        } else {
+            vec![]
+        };
+        let interior_joins = if framed {
+            interior_joins(puzzle_structure, edge_cells_total)
+        } else {
+            vec![]
+        };
+        let max_borders = self.puzzle_combinations.log10_border.len() - 1;
+        let max_middles = self.puzzle_combinations.log10_middle.len() - 1;
+
+        (0..=free)
+            .map(|placed| {
+                let cells = placed + hint_cells;
+                let joins = if plane {
+                    lattice.max_joins(cells)
+                } else {
+                    cells * lattice.sides() / 2
+                };
+                (0..=puzzle_structure.corners)
+                    .map(|corners| {
+                        (0..=puzzle_structure.edges)
+                            .map(|edges| {
+                                if corners + edges > placed
+                                    || placed - corners - edges > puzzle_structure.interiors
+                                {
+                                    return JoinLimit::default();
+                                }
+                                let corner_cells = corners + hint_corners;
+                                let edge_cells = edges + hint_edges;
+                                let frame_cells = corner_cells + edge_cells;
+                                let interior_cells = cells - frame_cells;
+                                let ring_joins = if !rectangle {
+                                    joins
+                                } else if ring_borders.is_empty() {
+                                    frame_cells.saturating_sub(1)
+                                } else {
+                                    ring_borders[corner_cells][edge_cells]
+                                };
+                                let middle_joins = if framed {
+                                    interior_joins[interior_cells][edge_cells]
+                                } else {
+                                    joins
+                                };
+                                let joins = joins
+                                    .min(ring_joins + middle_joins)
+                                    .saturating_sub((hint_borders + hint_middles) / 2);
+                                JoinLimit {
+                                    borders: ring_joins
+                                        .saturating_sub(hint_borders / 2)
+                                        .min(max_borders)
+                                        .min(joins),
+                                    middles: middle_joins
+                                        .saturating_sub(hint_middles / 2)
+                                        .min(max_middles)
+                                        .min(joins),
+                                    joins,
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Fewest log10 total and peak nodes over the depths after each number of
+    /// free cells placed, by the corner and edge cells among them. The counts
+    /// of each kind of cell only grow, so the bounds follow the cheapest way
+    /// to grow them to the full puzzle rather than the cheapest counts at each
+    /// depth on their own. Where a kind of cell holds several piece classes,
+    /// the pieces are taken from them in the cheapest split.
+    fn rest_bounds(&self, free: usize) -> Vec<Vec<Vec<(f64, f64)>>> {
+        let puzzle_combinations = self.puzzle_combinations;
+        let puzzle_structure = self.puzzle_structure;
+        let (corners, edges) = (puzzle_structure.corners, puzzle_structure.edges);
+        let join_limits = self.join_limits(free);
+        let [log10_corner, log10_edge, log10_interior] = [
+            LocationType::Corner,
+            LocationType::Edge,
+            LocationType::Interior,
+        ]
+        .map(|location_type| least_pieces(puzzle_combinations, location_type));
+        let nodes = |placed: usize, corners: usize, edges: usize| {
+            let limit = &join_limits[placed][corners][edges];
+            let joins = (0..=limit.borders)
+                .map(|borders| {
+                    puzzle_combinations.log10_border[borders]
+                        + puzzle_combinations.log10_middle[limit.middles.min(limit.joins - borders)]
+                })
+                .fold(f64::INFINITY, f64::min);
+            log10_corner[corners]
+                + log10_edge[edges]
+                + log10_interior[placed - corners - edges]
+                + joins
+        };
+
+        let infeasible = vec![vec![(f64::INFINITY, f64::INFINITY); edges + 1]; corners + 1];
+        let mut rest = vec![infeasible; free + 1];
+        rest[free][corners][edges] = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        (0..free).rev().for_each(|placed| {
+            (0..=corners.min(placed)).for_each(|placed_corners| {
+                (0..=edges.min(placed - placed_corners)).for_each(|placed_edges| {
+                    if placed - placed_corners - placed_edges > puzzle_structure.interiors {
+                        return;
+                    }
+                    rest[placed][placed_corners][placed_edges] = [
+                        (placed_corners + 1, placed_edges),
+                        (placed_corners, placed_edges + 1),
+                        (placed_corners, placed_edges),
+                    ]
+                    .iter()
+                    .filter(|(next_corners, next_edges)| {
+                        *next_corners <= corners
+                            && *next_edges <= edges
+                            && rest[placed + 1][*next_corners][*next_edges].0 < f64::INFINITY
+                    })
+                    .map(|(next_corners, next_edges)| {
+                        let next = nodes(placed + 1, *next_corners, *next_edges);
+                        let (rest_total, rest_peak) = rest[placed + 1][*next_corners][*next_edges];
+                        (add_log10s(&[next, rest_total]), next.max(rest_peak))
+                    })
+                    .fold((f64::INFINITY, f64::INFINITY), |best, bound| {
+                        (best.0.min(bound.0), best.1.min(bound.1))
+                    });
+                });
+            });
+        });
+        rest
+    }
+
+    /// Lower bound on the key of any full order that starts with the filled
+    /// cells, taking the fewest nodes the later depths could have.
+    fn estimate(
+        &self,
+        objective: Objective,
+        log10_total: f64,
+        log10_peak: f64,
+        used: &Used,
+        rest_bounds: &[Vec<Vec<(f64, f64)>>],
+    ) -> (f64, f64) {
+        let placed = used.corners + used.edges + used.interiors;
+        let (rest_total, rest_peak) = rest_bounds[placed][used.corners][used.edges];
+        order_key(
+            objective,
+            add_log10s(&[log10_total, rest_total]),
+            log10_peak.max(rest_peak),
+        )
+    }
+
+    fn key(&self, optimised: &OptimisedOrder, objective: Objective) -> (f64, f64) {
+        order_key(
+            objective,
+            optimised.log10_total,
+            optimised.search_nodes.log10_peak(),
+        )
+    }
+
+    /// Walks back from the full set, hints first, to recover the order.
+    fn order(&self, cells: &[Cell], reached: &HashMap<u64, Reached>, full: u64) -> Vec<Location> {
+        let mut order: Vec<Location> = vec![];
+        let mut filled = full;
+        while filled != 0 {
+            let last = reached[&filled].last;
+            order.push(cells[last].location.clone());
+            filled &= !(1 << last);
+        }
+        self.puzzle_structure
+            .locations()
+            .filter(|location| location.hint)
+            .for_each(|location| {
+                order.push(Location {
+                    x: location.x,
+                    y: location.y,
+                })
+            });
+        order.reverse();
+        order
+    }
+}
+
+/// Most middle joins that each number of interior cells and edge cells can
+/// share, where each edge cell meets one interior cell. Small interiors are
+/// searched in full for the most joins among the interior cells plus those
+/// to the frame they could reach, and larger ones take `Lattice::max_joins`
+/// plus the most joins to the frame of any cells.
+fn interior_joins(puzzle_structure: &PuzzleStructure, edges: usize) -> Vec<Vec<usize>> {
+    let interiors: Vec<&GridLocation> = puzzle_structure
+        .locations()
+        .filter(|location| location.location_type == LocationType::Interior)
+        .collect();
+    let mut index: HashMap<(usize, usize), usize> = HashMap::new();
+    interiors.iter().enumerate().for_each(|(bit, location)| {
+        index.insert((location.x, location.y), bit);
+    });
+    let neighbours: Vec<u64> = interiors
+        .iter()
+        .map(|location| {
+            location
+                .joins
+                .iter()
+                .filter_map(|join| index.get(&(join.x, join.y)))
+                .fold(0, |neighbours, bit| neighbours | 1 << bit)
+        })
+        .collect();
+    let frame: Vec<usize> = interiors
+        .iter()
+        .map(|location| {
+            location.joins.len()
+                - neighbours[index[&(location.x, location.y)]].count_ones() as usize
+        })
+        .collect();
+
+    // Most joins among the interior cells for each count and joins to the frame.
+    let most_frame: usize = frame.iter().sum();
+    let mut most = vec![vec![None; most_frame + 1]; interiors.len() + 1];
+    if interiors.len() <= 20 {
+        let mut joins = vec![(0u8, 0u8); 1 << interiors.len()];
+        (1..joins.len()).for_each(|set| {
+            let bit = set.trailing_zeros() as usize;
+            let rest = set & (set - 1);
+            let (inner, outer) = joins[rest];
+            let inner = inner + (neighbours[bit] & rest as u64).count_ones() as u8;
+            let outer = outer + frame[bit] as u8;
+            joins[set] = (inner, outer);
+            let entry = &mut most[set.count_ones() as usize][outer as usize];
+            *entry = Some(entry.map_or(inner as usize, |entry: usize| entry.max(inner as usize)));
+        });
+        most[0][0] = Some(0);
+    } else {
+        let mut frame = frame.clone();
+        frame.sort_by(|a, b| b.cmp(a));
+        (0..=interiors.len()).for_each(|cells| {
+            let outer: usize = frame.iter().take(cells).sum();
+            most[cells][outer] = Some(puzzle_structure.lattice.max_joins(cells));
+        });
+    }
+
+    most.iter()
+        .map(|by_frame| {
+            (0..=edges)
+                .map(|edges| {
+                    by_frame
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(outer, inner)| inner.map(|inner| inner + outer.min(edges)))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Most border joins that each number of corner and edge cells can share
+/// round the frame ring, given the number of edge cells along each side. A
+/// side adds one join fewer than its placed edges, plus one for a placed
+/// corner it meets, or both once the whole side is placed.
+fn ring_borders(sides: [usize; 4]) -> Vec<Vec<usize>> {
+    let edges: usize = sides.iter().sum();
+    let mut best = vec![vec![0; edges + 1]; 5];
+    (0..16usize).for_each(|corners| {
+        let placed = |corner: usize| corners & (1 << (corner % 4)) != 0;
+        let mut joins: Vec<Option<usize>> = vec![Some(0)];
+        sides.iter().enumerate().for_each(|(side, length)| {
+            let (start, end) = (placed(side), placed(side + 1));
+            let gain = |edges: usize| match edges {
+                0 if *length == 0 => usize::from(start && end),
+                0 => 0,
+                edges if edges == *length => edges - 1 + usize::from(start) + usize::from(end),
+                edges => edges - 1 + usize::from(start || end),
+            };
+            let mut next: Vec<Option<usize>> = vec![None; joins.len() + length];
+            joins.iter().enumerate().for_each(|(before, total)| {
+                if let Some(total) = total {
+                    (0..=*length).for_each(|edges| {
+                        let joins = total + gain(edges);
+                        let entry = &mut next[before + edges];
+                        *entry = Some(entry.map_or(joins, |entry| entry.max(joins)));
+                    });
+                }
+            });
+            joins = next;
+        });
+        let row = &mut best[corners.count_ones() as usize];
+        joins.iter().enumerate().for_each(|(edges, total)| {
+            row[edges] = row[edges].max(total.unwrap_or(0));
+        });
+    });
+    best
+}
+
+/// Fewest ways to lay `n` pieces of a location type, over every split of
+/// them between its piece classes.
+fn least_pieces(puzzle_combinations: &FastCombinations, location_type: LocationType) -> Vec<f64> {
+    puzzle_combinations
+        .piece_classes
+        .iter()
+        .zip(puzzle_combinations.log10_pieces.iter())
+        .filter(|(class, _)| class.location_type == location_type)
+        .fold(vec![0.0], |least, (_, log10_pieces)| {
+            (0..least.len() + log10_pieces.len() - 1)
+                .map(|pieces| {
+                    (pieces.saturating_sub(least.len() - 1)..=pieces.min(log10_pieces.len() - 1))
+                        .map(|class_pieces| {
+                            least[pieces - class_pieces] + log10_pieces[class_pieces]
+                        })
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect()
+        })
+}
+
+fn order_key(objective: Objective, log10_total: f64, log10_peak: f64) -> (f64, f64) {
+    match objective {
+        Objective::Total => (log10_total, log10_peak),
+        Objective::Peak => (log10_peak, log10_total),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExactOptimiser, Objective, ring_borders};
+    use crate::model::{
+        FastCombinations, Location, PuzzleStructure, SearchNodes, SearchOrder, SearchProgress,
+        Topology,
+    };
+
+    /// Best total and peak over every order of the free cells, and the best
+    /// total among the orders with the best peak.
+    fn brute_force(puzzle_structure: &PuzzleStructure) -> (f64, f64, f64) {
+        let puzzle_combinations = FastCombinations::new(puzzle_structure);
+        let mut free: Vec<Location> = puzzle_structure
+            .grid
+            .iter()
+            .flatten()
+            .filter(|location| !location.hint)
+            .map(|location| Location {
+                x: location.x,
+                y: location.y,
+            })
+            .collect();
+        let hints: Vec<Location> = puzzle_structure
+            .locations()
+            .filter(|location| location.hint)
+            .map(|location| Location {
+                x: location.x,
+                y: location.y,
+            })
+            .collect();
+
+        let mut best = (f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        permute(&mut free, 0, &mut |order| {
+            let mut full = hints.clone();
+            full.extend(order.iter().cloned());
+            let search_order =
+                SearchOrder::from_locations(puzzle_structure.width, puzzle_structure.height, full)
+                    .unwrap();
+            let search_progress = SearchProgress::new(puzzle_structure, &search_order);
+            let search_nodes = SearchNodes::new(&puzzle_combinations, &search_progress);
+            let (total, peak) = (search_nodes.log10_total(), search_nodes.log10_peak());
+            best.0 = best.0.min(total);
+            if peak < best.1 - 1e-9 {
+                best.2 = total;
+            } else if peak < best.1 + 1e-9 {
+                best.2 = best.2.min(total);
+            }
+            best.1 = best.1.min(peak);
+        });
+        best
+    }
+
+    fn permute(order: &mut Vec<Location>, start: usize, visit: &mut impl FnMut(&[Location])) {
+        if start == order.len() {
+            visit(order);
+            return;
+        }
+        (start..order.len()).for_each(|index| {
+            order.swap(start, index);
+            permute(order, start + 1, visit);
+            order.swap(start, index);
+        });
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut hinted = PuzzleStructure::new(3, 3, 2, 2);
+        hinted.set_hints(&[Location { x: 1, y: 1 }]).unwrap();
+        let cylinder = PuzzleStructure::new(3, 2, 2, 1)
+            .with_topology(Topology::Cylinder)
+            .unwrap();
+        [PuzzleStructure::new(3, 2, 2, 1), hinted, cylinder]
+            .iter()
+            .for_each(|puzzle_structure| {
+                let (best_total, best_peak, peak_total) = brute_force(puzzle_structure);
+                let puzzle_combinations = FastCombinations::new(puzzle_structure);
+                let optimiser = ExactOptimiser::new(puzzle_structure, &puzzle_combinations);
+
+                let total = optimiser.optimise(Objective::Total).unwrap();
+                assert!((total.log10_total - best_total).abs() < 1e-9);
+                let peak = optimiser.optimise(Objective::Peak).unwrap();
+                assert!((peak.search_nodes.log10_peak() - best_peak).abs() < 1e-9);
+                assert!((peak.log10_total - peak_total).abs() < 1e-9);
+                assert_eq!(peak.search_order.order.len(), puzzle_structure.cells());
+            });
+    }
+
+    #[test]
+    fn test_peak_ties() {
+        // The cheapest way by peak to some sets of filled cells here leads to
+        // a higher total than another with the same final peak.
+        let puzzle_structure = PuzzleStructure::new(4, 4, 3, 2);
+        let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+        let optimiser = ExactOptimiser::new(&puzzle_structure, &puzzle_combinations);
+        let first = optimiser.search(Objective::Peak, None).unwrap();
+        let peak = optimiser.optimise(Objective::Peak).unwrap();
+        let total = optimiser.optimise(Objective::Total).unwrap();
+        assert!((peak.search_nodes.log10_peak() - first.search_nodes.log10_peak()).abs() < 1e-9);
+        assert!(peak.log10_total < first.log10_total - 1e-3);
+        assert!(peak.log10_total >= total.log10_total - 1e-9);
+    }
+
+    #[test]
+    fn test_state_limit() {
+        let puzzle_structure = PuzzleStructure::new(5, 5, 4, 4);
+        let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+        let mut optimiser = ExactOptimiser::new(&puzzle_structure, &puzzle_combinations);
+        optimiser.max_states = 10;
+        assert!(optimiser.optimise(Objective::Total).is_err());
+    }
+
+    #[test]
+    fn test_ring_borders() {
+        let borders = ring_borders([4, 3, 4, 3]);
+        assert_eq!(borders[0][0], 0);
+        assert_eq!(borders[4][0], 0);
+        // Every edge without a corner leaves the four sides apart.
+        assert_eq!(borders[0][14], 10);
+        assert_eq!(borders[2][4], 5);
+        assert_eq!(borders[4][14], 18);
+    }
+
+    #[test]
+    fn test_six_by_six() {
+        let puzzle_structure = PuzzleStructure::new(6, 6, 2, 4);
+        let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+        let optimiser = ExactOptimiser::new(&puzzle_structure, &puzzle_combinations);
+        let total = optimiser.optimise(Objective::Total).unwrap();
+        assert_eq!(total.search_order.order.len(), 36);
+    }
+}
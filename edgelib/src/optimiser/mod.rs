@@ -0,0 +1,5 @@
+mod exact_order;
+mod order_optimiser;
+
+pub use exact_order::*;
+pub use order_optimiser::*;
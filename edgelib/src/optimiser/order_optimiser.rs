@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::model::{
+    FastCombinations, JoinType, Location, LocationType, NodeCombinatorics, PuzzleStructure,
+    SearchNodes, SearchOption, SearchOrder, SearchProgress, Used, add_log10s,
+};
+
+/// Searches the space of cell orders for the one with the lowest total
+/// expected nodes, `SearchNodes::log10_total`.
+pub struct OrderOptimiser<'a> {
+    puzzle_structure: &'a PuzzleStructure,
+    puzzle_combinations: &'a FastCombinations,
+    /// Only place cells that share a join with a cell already placed.
+    pub connected: bool,
+}
+
+pub enum OptimiserMethod {
+    /// Always place the cell that gives the fewest nodes at the next depth.
+    Greedy,
+    /// Keep the given number of best partial orders at each depth.
+    Beam(usize),
+    /// Anneal from the best of the greedy and built-in orders by swapping and
+    /// moving cells.
+    Annealing { iterations: usize, seed: u64 },
+}
+
+pub struct OptimisedOrder {
+    pub search_order: SearchOrder,
+    pub search_nodes: SearchNodes,
+    pub log10_total: f64,
+}
+
+#[derive(Clone)]
+struct Partial {
+    filled: Vec<bool>,
+    order: Vec<Location>,
+    used: Used,
+    log10_total: f64,
+}
+
+impl<'a> OrderOptimiser<'a> {
+    pub fn new(
+        puzzle_structure: &'a PuzzleStructure,
+        puzzle_combinations: &'a FastCombinations,
+    ) -> Self {
+        OrderOptimiser {
+            puzzle_structure,
+            puzzle_combinations,
+            connected: false,
+        }
+    }
+
+    pub fn optimise(&self, method: &OptimiserMethod) -> OptimisedOrder {
+        let order = match method {
+            OptimiserMethod::Greedy => self.beam(1),
+            OptimiserMethod::Beam(width) => self.beam((*width).max(1)),
+            OptimiserMethod::Annealing { iterations, seed } => {
+                self.anneal(self.best_start(), *iterations, *seed)
+            }
+        };
+        self.finish(order)
+    }
+
+    /// Profiles an order that covers every cell.
+    pub fn evaluate(&self, order: &[Location]) -> f64 {
+        let search_order = SearchOrder {
+            width: self.puzzle_structure.width,
+            height: self.puzzle_structure.height,
+            size: order.len(),
+            order: order.to_vec(),
+        };
+        let search_progress = SearchProgress::new(self.puzzle_structure, &search_order);
+        SearchNodes::new(self.puzzle_combinations, &search_progress).log10_total()
+    }
+
+    pub(crate) fn finish(&self, order: Vec<Location>) -> OptimisedOrder {
+        let search_order = SearchOrder::from_structure_locations(self.puzzle_structure, order)
+            .expect("Optimiser produced an incomplete order");
+        let search_progress = SearchProgress::new(self.puzzle_structure, &search_order);
+        let search_nodes = SearchNodes::new(self.puzzle_combinations, &search_progress);
+        let log10_total = search_nodes.log10_total();
+        OptimisedOrder {
+            search_order,
+            search_nodes,
+            log10_total,
+        }
+    }
+
+    /// Greedy order or whichever built-in order beats it, with hinted cells
+    /// moved to the front.
+    pub(crate) fn best_start(&self) -> Vec<Location> {
+        let greedy = self.beam(1);
+        let greedy_total = self.evaluate(&greedy);
+        SearchOption::all()
+            .into_iter()
+            .map(|search_option| {
+                let search_order = SearchOrder::for_structure(self.puzzle_structure, search_option);
+                let (mut order, free): (Vec<Location>, Vec<Location>) = search_order
+                    .order
+                    .into_iter()
+                    .partition(|location| self.puzzle_structure.grid[location.x][location.y].hint);
+                order.extend(free);
+                order
+            })
+            .filter(|order| !self.connected || self.is_connected(order))
+            .map(|order| {
+                let total = self.evaluate(&order);
+                (order, total)
+            })
+            .fold((greedy, greedy_total), |best, next| {
+                if next.1 < best.1 { next } else { best }
+            })
+            .0
+    }
+
+    fn start(&self) -> Partial {
+        let height = self.puzzle_structure.height;
+        let mut partial = Partial {
+            filled: vec![false; self.puzzle_structure.width * height],
+            order: vec![],
+            used: Used::empty(self.puzzle_structure),
+            log10_total: self
+                .puzzle_combinations
+                .log10_nodes(&Used::empty(self.puzzle_structure)),
+        };
+        self.puzzle_structure
+            .locations()
+            .filter(|location| location.hint)
+            .for_each(|location| {
+                partial.filled[location.x * height + location.y] = true;
+                partial.order.push(Location {
+                    x: location.x,
+                    y: location.y,
+                });
+            });
+        partial
+    }
+
+    /// Cells that may be placed next.
+    fn candidates(&self, partial: &Partial) -> Vec<Location> {
+        let height = self.puzzle_structure.height;
+        let any_filled = partial.filled.iter().any(|filled| *filled);
+        self.puzzle_structure
+            .locations()
+            .filter(|location| !partial.filled[location.x * height + location.y])
+            .filter(|location| {
+                !self.connected
+                    || !any_filled
+                    || location
+                        .joins
+                        .iter()
+                        .any(|join| partial.filled[join.x * height + join.y])
+            })
+            .map(|location| Location {
+                x: location.x,
+                y: location.y,
+            })
+            .collect()
+    }
+
+    fn place(&self, partial: &Partial, location: &Location) -> Partial {
+        let height = self.puzzle_structure.height;
+        let grid_location = &self.puzzle_structure.grid[location.x][location.y];
+        let mut next = partial.clone();
+        grid_location.joins.iter().for_each(|join| {
+            if partial.filled[join.x * height + join.y] {
+                match join.join_type {
+                    JoinType::Border => next.used.borders += 1,
+                    JoinType::Middle => next.used.middles += 1,
+                }
+            }
+        });
+        match grid_location.location_type {
+            LocationType::Corner => next.used.corners += 1,
+            LocationType::Edge => next.used.edges += 1,
+            LocationType::Interior => next.used.interiors += 1,
+        }
+        next.used.pieces[grid_location.piece_class] += 1;
+        next.filled[location.x * height + location.y] = true;
+        next.order.push(location.clone());
+        next.log10_total = add_log10s(&[
+            partial.log10_total,
+            self.puzzle_combinations.log10_nodes(&next.used),
+        ]);
+        next
+    }
+
+    fn beam(&self, width: usize) -> Vec<Location> {
+        let cells = self.puzzle_structure.cells();
+        let mut beam: Vec<Partial> = vec![self.start()];
+        while beam[0].order.len() < cells {
+            let mut expanded: HashMap<Vec<bool>, Partial> = HashMap::new();
+            beam.iter().for_each(|partial| {
+                self.candidates(partial).iter().for_each(|location| {
+                    let next = self.place(partial, location);
+                    match expanded.get(&next.filled) {
+                        Some(existing) if existing.log10_total <= next.log10_total => {}
+                        _ => {
+                            expanded.insert(next.filled.clone(), next);
+                        }
+                    }
+                });
+            });
+            if expanded.is_empty() {
+                panic!("No cell can be placed next to the cells already placed");
+            }
+            let mut next_beam: Vec<Partial> = expanded.into_values().collect();
+            next_beam.sort_by(|a, b| {
+                a.log10_total
+                    .total_cmp(&b.log10_total)
+                    .then_with(|| order_key(&a.order).cmp(&order_key(&b.order)))
+            });
+            next_beam.truncate(width);
+            beam = next_beam;
+        }
+        beam.swap_remove(0).order
+    }
+
+    fn anneal(&self, order: Vec<Location>, iterations: usize, seed: u64) -> Vec<Location> {
+        let hints = self.puzzle_structure.hints();
+        let free = order.len() - hints;
+        if free < 2 {
+            return order;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let start_temperature: f64 = 1.0;
+        let end_temperature: f64 = 0.001;
+
+        let mut current = order;
+        let mut current_total = self.evaluate(&current);
+        let mut best = current.clone();
+        let mut best_total = current_total;
+
+        (0..iterations).for_each(|iteration| {
+            let temperature = start_temperature
+                * (end_temperature / start_temperature)
+                    .powf(iteration as f64 / iterations.max(1) as f64);
+            let first = hints + rng.random_range(0..free);
+            let second = hints + rng.random_range(0..free);
+            if first == second {
+                return;
+            }
+
+            let mut candidate = current.clone();
+            if rng.random_bool(0.5) {
+                candidate.swap(first, second);
+            } else {
+                let location = candidate.remove(first);
+                candidate.insert(second, location);
+            }
+            if self.connected && !self.is_connected(&candidate) {
+                return;
+            }
+
+            let candidate_total = self.evaluate(&candidate);
+            let change = candidate_total - current_total;
+            if change <= 0.0 || rng.random::<f64>() < (-change / temperature).exp() {
+                current = candidate;
+                current_total = candidate_total;
+                if current_total < best_total {
+                    best = current.clone();
+                    best_total = current_total;
+                }
+            }
+        });
+        best
+    }
+
+    /// True if every cell after the first shares a join with an earlier one.
+    /// Hinted cells count as placed from the start.
+    fn is_connected(&self, order: &[Location]) -> bool {
+        let height = self.puzzle_structure.height;
+        let mut filled = self.start().filled;
+        let mut any_filled = filled.iter().any(|filled| *filled);
+        order.iter().all(|location| {
+            let grid_location = &self.puzzle_structure.grid[location.x][location.y];
+            if grid_location.hint {
+                return true;
+            }
+            let joined = !any_filled
+                || grid_location
+                    .joins
+                    .iter()
+                    .any(|join| filled[join.x * height + join.y]);
+            filled[location.x * height + location.y] = true;
+            any_filled = true;
+            joined
+        })
+    }
+}
+
+/// Breaks ties between equally good partial orders so results do not depend
+/// on hash map ordering.
+fn order_key(order: &[Location]) -> Vec<(usize, usize)> {
+    order
+        .iter()
+        .map(|location| (location.y, location.x))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OptimiserMethod, OrderOptimiser};
+    use crate::model::{
+        FastCombinations, PuzzleStructure, SearchNodes, SearchOption, SearchOrder, SearchProgress,
+    };
+
+    #[test]
+    fn test_optimise_beats_scan() {
+        let puzzle_structure = PuzzleStructure::new(5, 4, 3, 4);
+        let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+        let search_order = SearchOrder::new(5, 4, SearchOption::ScanRows);
+        let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+        let scan_total = SearchNodes::new(&puzzle_combinations, &search_progress).log10_total();
+
+        let optimiser = OrderOptimiser::new(&puzzle_structure, &puzzle_combinations);
+        let greedy = optimiser.optimise(&OptimiserMethod::Greedy);
+        let beam = optimiser.optimise(&OptimiserMethod::Beam(4));
+        let annealed = optimiser.optimise(&OptimiserMethod::Annealing {
+            iterations: 500,
+            seed: 1,
+        });
+
+        assert_eq!(greedy.search_order.order.len(), 20);
+        assert!((greedy.log10_total - optimiser.evaluate(&greedy.search_order.order)).abs() < 1e-9);
+        assert!(beam.log10_total <= greedy.log10_total + 1e-9);
+        assert!(annealed.log10_total <= greedy.log10_total + 1e-9);
+        assert!(annealed.log10_total <= scan_total + 1e-9);
+    }
+
+    #[test]
+    fn test_connected() {
+        let puzzle_structure = PuzzleStructure::new(4, 4, 2, 3);
+        let puzzle_combinations = FastCombinations::new(&puzzle_structure);
+        let mut optimiser = OrderOptimiser::new(&puzzle_structure, &puzzle_combinations);
+        optimiser.connected = true;
+        let annealed = optimiser.optimise(&OptimiserMethod::Annealing {
+            iterations: 300,
+            seed: 2,
+        });
+        assert!(optimiser.is_connected(&annealed.search_order.order));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::instance::{Placement, PuzzleInstance};
+use crate::model::{COORDINATES, Lattice, PuzzleStructure, SearchOrder};
+
+/// Depth-first edge matching solver that fills cells in the sequence given by
+/// a `SearchOrder`. Fixed pieces are placed before the search starts, so depth
+/// counts only the cells that are searched.
+pub struct BacktrackSolver<'a> {
+    puzzle_instance: &'a PuzzleInstance,
+    cells: Vec<(usize, usize)>,
+    candidates: Vec<Vec<(usize, usize)>>,
+    neighbours: Vec<Vec<(usize, (usize, usize))>>,
+    board: Vec<Vec<Option<(usize, usize)>>>,
+    used: Vec<bool>,
+    conflict: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SolveResult {
+    /// Number of consistent partial placements at each depth, starting with
+    /// the empty board at depth 0.
+    pub nodes: Vec<u64>,
+    /// Placement of every piece, in the order of `PuzzleInstance::pieces`.
+    pub solutions: Vec<Vec<Placement>>,
+    /// False if a limit stopped the search before the tree was exhausted.
+    pub complete: bool,
+}
+
+struct Limits {
+    max_solutions: Option<usize>,
+    max_nodes: Option<u64>,
+    total_nodes: u64,
+    stopped: bool,
+}
+
+impl<'a> BacktrackSolver<'a> {
+    pub fn new(
+        puzzle_instance: &'a PuzzleInstance,
+        puzzle_structure: &PuzzleStructure,
+        search_order: &SearchOrder,
+    ) -> Self {
+        if search_order.width != puzzle_instance.width
+            || search_order.height != puzzle_instance.height
+        {
+            panic!("Search order and Puzzle instance size needs to match");
+        }
+        if puzzle_structure.width != puzzle_instance.width
+            || puzzle_structure.height != puzzle_instance.height
+        {
+            panic!("Puzzle structure and Puzzle instance size needs to match");
+        }
+        if puzzle_structure.lattice != Lattice::Square {
+            panic!("Only square pieces can be solved");
+        }
+
+        let mut board: Vec<Vec<Option<(usize, usize)>>> =
+            vec![vec![None; puzzle_instance.height]; puzzle_instance.width];
+        let mut used: Vec<bool> = vec![false; puzzle_instance.pieces.len()];
+        let mut conflict = false;
+        puzzle_instance
+            .pieces
+            .iter()
+            .enumerate()
+            .for_each(|(index, piece)| {
+                if let Some(placement) = piece.fixed {
+                    if board[placement.x][placement.y].is_some() {
+                        conflict = true;
+                    }
+                    board[placement.x][placement.y] = Some((index, placement.rotation));
+                    used[index] = true;
+                }
+            });
+
+        let cells: Vec<(usize, usize)> = search_order
+            .order
+            .iter()
+            .filter(|location| board[location.x][location.y].is_none())
+            .map(|location| (location.x, location.y))
+            .collect();
+
+        let candidates: Vec<Vec<(usize, usize)>> = cells
+            .iter()
+            .map(|(x, y)| {
+                puzzle_instance
+                    .pieces
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| !used[*index])
+                    .flat_map(|(index, piece)| {
+                        (0..COORDINATES.len())
+                            .filter(|rotation| {
+                                puzzle_instance.fits_frame(
+                                    puzzle_structure,
+                                    piece,
+                                    *x,
+                                    *y,
+                                    *rotation,
+                                )
+                            })
+                            .map(move |rotation| (index, rotation))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let neighbours: Vec<Vec<(usize, (usize, usize))>> = cells
+            .iter()
+            .map(|(x, y)| {
+                (0..COORDINATES.len())
+                    .filter_map(|direction| {
+                        puzzle_structure
+                            .neighbour(*x, *y, direction)
+                            .map(|neighbour| (direction, neighbour))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        conflict |= !fixed_pieces_match(puzzle_instance, puzzle_structure, &board);
+
+        BacktrackSolver {
+            puzzle_instance,
+            cells,
+            candidates,
+            neighbours,
+            board,
+            used,
+            conflict,
+        }
+    }
+
+    /// Runs the search, stopping early once either limit is reached.
+    pub fn solve(&mut self, max_solutions: Option<usize>, max_nodes: Option<u64>) -> SolveResult {
+        let mut result = SolveResult {
+            nodes: vec![0; self.cells.len() + 1],
+            solutions: vec![],
+            complete: true,
+        };
+        result.nodes[0] = 1;
+        if self.conflict {
+            return result;
+        }
+
+        let mut limits = Limits {
+            max_solutions,
+            max_nodes,
+            total_nodes: 0,
+            stopped: false,
+        };
+        self.search(0, &mut result, &mut limits);
+        result.complete = !limits.stopped;
+        result
+    }
+
+    fn search(&mut self, depth: usize, result: &mut SolveResult, limits: &mut Limits) {
+        if depth == self.cells.len() {
+            result.solutions.push(self.solution());
+            if limits
+                .max_solutions
+                .is_some_and(|max_solutions| result.solutions.len() >= max_solutions)
+            {
+                limits.stopped = true;
+            }
+            return;
+        }
+
+        let (x, y) = self.cells[depth];
+        for candidate in 0..self.candidates[depth].len() {
+            if limits.stopped {
+                return;
+            }
+            let (piece, rotation) = self.candidates[depth][candidate];
+            if self.used[piece] || !self.matches(depth, piece, rotation) {
+                continue;
+            }
+
+            result.nodes[depth + 1] += 1;
+            limits.total_nodes += 1;
+            if limits
+                .max_nodes
+                .is_some_and(|max_nodes| limits.total_nodes >= max_nodes)
+            {
+                limits.stopped = true;
+            }
+
+            self.board[x][y] = Some((piece, rotation));
+            self.used[piece] = true;
+            self.search(depth + 1, result, limits);
+            self.used[piece] = false;
+            self.board[x][y] = None;
+        }
+    }
+
+    /// Number of cells the search fills.
+    pub(super) fn depths(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// True if the fixed pieces already disagree, leaving nothing to search.
+    pub(super) fn conflict(&self) -> bool {
+        self.conflict
+    }
+
+    /// Unused pieces and rotations that fit the cell at `depth` given the
+    /// pieces on the board.
+    pub(super) fn children(&self, depth: usize) -> Vec<(usize, usize)> {
+        self.candidates[depth]
+            .iter()
+            .filter(|(piece, rotation)| {
+                !self.used[*piece] && self.matches(depth, *piece, *rotation)
+            })
+            .copied()
+            .collect()
+    }
+
+    pub(super) fn place(&mut self, depth: usize, piece: usize, rotation: usize) {
+        let (x, y) = self.cells[depth];
+        self.board[x][y] = Some((piece, rotation));
+        self.used[piece] = true;
+    }
+
+    pub(super) fn lift(&mut self, depth: usize) {
+        let (x, y) = self.cells[depth];
+        if let Some((piece, _)) = self.board[x][y].take() {
+            self.used[piece] = false;
+        }
+    }
+
+    /// True if the piece agrees with every neighbour already on the board.
+    fn matches(&self, depth: usize, piece: usize, rotation: usize) -> bool {
+        let sides = COORDINATES.len();
+        let pieces = &self.puzzle_instance.pieces;
+        self.neighbours[depth]
+            .iter()
+            .all(|(direction, (x, y))| match self.board[*x][*y] {
+                Some((other, other_rotation)) => {
+                    pieces[piece].side(*direction, rotation)
+                        == pieces[other].side((direction + sides / 2) % sides, other_rotation)
+                }
+                None => true,
+            })
+    }
+
+    fn solution(&self) -> Vec<Placement> {
+        let mut placements: Vec<Placement> = vec![
+            Placement {
+                x: 0,
+                y: 0,
+                rotation: 0
+            };
+            self.puzzle_instance.pieces.len()
+        ];
+        self.board.iter().enumerate().for_each(|(x, column)| {
+            column.iter().enumerate().for_each(|(y, cell)| {
+                if let Some((piece, rotation)) = cell {
+                    placements[*piece] = Placement {
+                        x,
+                        y,
+                        rotation: *rotation,
+                    };
+                }
+            })
+        });
+        placements
+    }
+}
+
+fn fixed_pieces_match(
+    puzzle_instance: &PuzzleInstance,
+    puzzle_structure: &PuzzleStructure,
+    board: &[Vec<Option<(usize, usize)>>],
+) -> bool {
+    let sides = COORDINATES.len();
+    let pieces = &puzzle_instance.pieces;
+    (0..puzzle_instance.width).all(|x| {
+        (0..puzzle_instance.height).all(|y| {
+            let Some((piece, rotation)) = board[x][y] else {
+                return true;
+            };
+            (0..sides).all(|direction| {
+                let Some((test_x, test_y)) = puzzle_structure.neighbour(x, y, direction) else {
+                    return true;
+                };
+                match board[test_x][test_y] {
+                    Some((other, other_rotation)) => {
+                        pieces[piece].side(direction, rotation)
+                            == pieces[other].side((direction + sides / 2) % sides, other_rotation)
+                    }
+                    None => true,
+                }
+            })
+        })
+    })
+}
+
+impl SolveResult {
+    pub fn log10_nodes(&self) -> Vec<f64> {
+        self.nodes
+            .iter()
+            .map(|count| (*count as f64).log10())
+            .collect()
+    }
+
+    /// Copy of the instance with every piece fixed where the solution put it.
+    pub fn solved_instance(
+        &self,
+        puzzle_instance: &PuzzleInstance,
+        solution: usize,
+    ) -> PuzzleInstance {
+        let mut solved = puzzle_instance.clone();
+        solved
+            .pieces
+            .iter_mut()
+            .zip(self.solutions[solution].iter())
+            .for_each(|(piece, placement)| piece.fixed = Some(*placement));
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BacktrackSolver;
+    use crate::instance::PuzzleInstance;
+    use crate::model::{SearchOption, SearchOrder};
+
+    const THREE_BY_THREE: &str = "\
+0 1 2 0
+0 3 4 1
+0 0 5 3
+2 6 7 0
+4 8 9 6
+5 0 10 8
+7 11 0 0
+9 12 0 11
+10 0 0 12
+";
+
+    #[test]
+    fn test_solve_counts() {
+        let puzzle_instance = PuzzleInstance::parse(THREE_BY_THREE).unwrap();
+        let puzzle_structure = puzzle_instance.structure().unwrap();
+        let search_order = SearchOrder::new(3, 3, SearchOption::ScanRows);
+
+        let mut solver = BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+        let result = solver.solve(None, None);
+
+        // Every solution of a square board can be turned a quarter at a time.
+        assert!(result.complete);
+        assert_eq!(result.solutions.len(), 4);
+        assert_eq!(result.nodes.len(), 10);
+        assert_eq!(result.nodes[0], 1);
+        assert_eq!(result.nodes[1], 4);
+        assert_eq!(result.nodes[9], 4);
+
+        let solved = result.solved_instance(&puzzle_instance, 0);
+        let mut fixed_solver = BacktrackSolver::new(&solved, &puzzle_structure, &search_order);
+        let fixed_result = fixed_solver.solve(None, None);
+        assert_eq!(fixed_result.nodes, vec![1]);
+        assert_eq!(fixed_result.solutions.len(), 1);
+    }
+
+    #[test]
+    fn test_solve_limits() {
+        let puzzle_instance = PuzzleInstance::parse(THREE_BY_THREE).unwrap();
+        let puzzle_structure = puzzle_instance.structure().unwrap();
+        let search_order = SearchOrder::new(3, 3, SearchOption::SpiralIn);
+
+        let mut solver = BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+        let result = solver.solve(None, Some(3));
+        assert!(!result.complete);
+        assert_eq!(result.nodes.iter().skip(1).sum::<u64>(), 3);
+    }
+}
@@ -0,0 +1,175 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use super::BacktrackSolver;
+
+/// Estimated size of a backtrack tree from random probes, after Knuth's
+/// method. Each probe walks from the root to a leaf, taking one child at
+/// every depth, and the product of the inverse choice probabilities is an
+/// unbiased estimate of the nodes at each depth it reaches.
+#[derive(Clone, Debug)]
+pub struct TreeEstimate {
+    /// Mean estimated nodes at each depth, starting with the empty board.
+    pub nodes: Vec<f64>,
+    /// Standard error of each mean.
+    pub standard_error: Vec<f64>,
+    pub probes: usize,
+}
+
+/// Normal quantile for a two-sided 95% confidence interval.
+pub const CONFIDENCE_95: f64 = 1.959964;
+
+impl BacktrackSolver<'_> {
+    /// Runs `probes` random probes from a generator seeded with `seed`. With
+    /// `importance` set, each child is taken in proportion to one more than
+    /// its own number of children, which favours the busier subtrees while
+    /// keeping the estimate unbiased.
+    pub fn estimate(&mut self, probes: usize, seed: u64, importance: bool) -> TreeEstimate {
+        if probes == 0 {
+            panic!("There must be at least one probe");
+        }
+        let depths = self.depths();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sums: Vec<f64> = vec![0.0; depths + 1];
+        let mut squares: Vec<f64> = vec![0.0; depths + 1];
+
+        (0..probes).for_each(|_| {
+            let weights = self.probe(&mut rng, importance);
+            weights.iter().enumerate().for_each(|(depth, weight)| {
+                sums[depth] += weight;
+                squares[depth] += weight * weight;
+            });
+        });
+
+        let count = probes as f64;
+        let nodes: Vec<f64> = sums.iter().map(|sum| sum / count).collect();
+        let standard_error: Vec<f64> = nodes
+            .iter()
+            .zip(squares.iter())
+            .map(|(mean, square)| {
+                if probes == 1 {
+                    return 0.0;
+                }
+                let variance = ((square - count * mean * mean) / (count - 1.0)).max(0.0);
+                (variance / count).sqrt()
+            })
+            .collect();
+
+        TreeEstimate {
+            nodes,
+            standard_error,
+            probes,
+        }
+    }
+
+    /// Estimated nodes at each depth from one probe, zero past a dead end.
+    fn probe(&mut self, rng: &mut StdRng, importance: bool) -> Vec<f64> {
+        let depths = self.depths();
+        let mut weights: Vec<f64> = vec![0.0; depths + 1];
+        weights[0] = 1.0;
+        if self.conflict() {
+            return weights;
+        }
+
+        let mut weight = 1.0;
+        let mut placed = 0;
+        while placed < depths {
+            let children = self.children(placed);
+            if children.is_empty() {
+                break;
+            }
+            let scores: Vec<f64> = if importance {
+                children
+                    .iter()
+                    .map(|(piece, rotation)| {
+                        if placed + 1 == depths {
+                            return 1.0;
+                        }
+                        self.place(placed, *piece, *rotation);
+                        let grandchildren = self.children(placed + 1).len();
+                        self.lift(placed);
+                        1.0 + grandchildren as f64
+                    })
+                    .collect()
+            } else {
+                vec![1.0; children.len()]
+            };
+            let total: f64 = scores.iter().sum();
+            let mut pick = rng.random::<f64>() * total;
+            let chosen = scores
+                .iter()
+                .position(|score| {
+                    pick -= score;
+                    pick < 0.0
+                })
+                .unwrap_or(children.len() - 1);
+
+            weight *= total / scores[chosen];
+            let (piece, rotation) = children[chosen];
+            self.place(placed, piece, rotation);
+            placed += 1;
+            weights[placed] = weight;
+        }
+        (0..placed).rev().for_each(|depth| self.lift(depth));
+        weights
+    }
+}
+
+impl TreeEstimate {
+    pub fn log10_nodes(&self) -> Vec<f64> {
+        self.nodes.iter().map(|nodes| nodes.log10()).collect()
+    }
+
+    /// log10 of the lower and upper ends of the interval `z` standard errors
+    /// either side of each mean. A lower end at or below zero gives `-inf`.
+    pub fn log10_bounds(&self, z: f64) -> (Vec<f64>, Vec<f64>) {
+        self.nodes
+            .iter()
+            .zip(self.standard_error.iter())
+            .map(|(nodes, error)| {
+                let lower = nodes - z * error;
+                (
+                    if lower > 0.0 {
+                        lower.log10()
+                    } else {
+                        f64::NEG_INFINITY
+                    },
+                    (nodes + z * error).log10(),
+                )
+            })
+            .unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::{GeneratedPuzzle, PuzzleInstance};
+    use crate::model::{PuzzleStructure, SearchOption, SearchOrder};
+    use crate::solver::BacktrackSolver;
+
+    #[test]
+    fn test_estimate_tree() {
+        let puzzle_structure = PuzzleStructure::new(4, 4, 2, 3);
+        let generated = GeneratedPuzzle::new(&puzzle_structure, 7).unwrap();
+        let puzzle_instance: PuzzleInstance = generated.puzzle_instance;
+        let search_order = SearchOrder::new(4, 4, SearchOption::ScanRows);
+        let mut solver = BacktrackSolver::new(&puzzle_instance, &puzzle_structure, &search_order);
+        let result = solver.solve(None, None);
+
+        let uniform = solver.estimate(4000, 1, false);
+        // The first choice is made from every child, so the first depth is exact.
+        assert_eq!(uniform.nodes[1], result.nodes[1] as f64);
+        assert_eq!(uniform.standard_error[1], 0.0);
+
+        [uniform, solver.estimate(4000, 1, true)]
+            .iter()
+            .for_each(|estimate| {
+                let total: f64 = estimate.nodes.iter().sum();
+                let actual = result.nodes.iter().sum::<u64>() as f64;
+                assert!((total / actual - 1.0).abs() < 0.2);
+                let (lower, upper) = estimate.log10_bounds(super::CONFIDENCE_95);
+                assert!(lower[1] <= upper[1]);
+            });
+    }
+}
@@ -0,0 +1,5 @@
+mod backtrack;
+mod knuth;
+
+pub use backtrack::*;
+pub use knuth::*;
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use crate::model::{
+    NodeCombinatorics, PuzzleStructure, SearchNodes, SearchOrder, SearchOrderStrategy,
+    SearchProgress, SearchSummary, Used,
+};
+
+use super::skewed_join_counts;
+
+/// The fewest middle edge types at which the expected solutions of a board
+/// fall to a target, for one skew of the middle join counts.
+#[derive(Clone, Debug)]
+pub struct CriticalPoint {
+    pub skew: f64,
+    pub middle_types: usize,
+    pub middle_join_counts: Vec<usize>,
+    /// Where the log10 expected solutions cross the target, interpolated
+    /// between `middle_types - 1` and `middle_types`.
+    pub middle_estimate: f64,
+    pub log10_solutions: f64,
+    /// Summary of each search order at `middle_types`.
+    pub summaries: Vec<SearchSummary>,
+}
+
+/// Searches the middle edge types of a `width` by `height` board with
+/// `border_types` border types, for each skew of the middle join counts, and
+/// returns the points where the log10 expected solutions first drop to
+/// `log10_target` or below. Skews that never reach the target are left out.
+/// `combinations` gives the combinatorics of each structure tried. Fails if a
+/// strategy does not give a valid order for the board.
+pub fn critical_points<C: NodeCombinatorics<Progress = SearchProgress>>(
+    width: usize,
+    height: usize,
+    border_types: usize,
+    skews: &[f64],
+    log10_target: f64,
+    strategies: &[Arc<dyn SearchOrderStrategy>],
+    combinations: impl Fn(&PuzzleStructure) -> C,
+) -> Result<Vec<CriticalPoint>, String> {
+    if width <= 1 || height <= 1 {
+        panic!("Width and height must be greater than 1");
+    }
+
+    let mut points: Vec<CriticalPoint> = vec![];
+    for skew in skews {
+        let structure = |middle_types: usize| {
+            let mut puzzle_structure =
+                PuzzleStructure::new(width, height, border_types, middle_types);
+            puzzle_structure.middle_join_counts =
+                skewed_join_counts(puzzle_structure.middle_joins, middle_types, *skew);
+            puzzle_structure
+        };
+        let middle_joins = structure(1).middle_joins;
+
+        let mut previous = f64::INFINITY;
+        for middle_types in 1..=middle_joins.max(1) {
+            let puzzle_structure = structure(middle_types);
+            let puzzle_combinations = combinations(&puzzle_structure);
+            let log10_solutions = log10_solutions(&puzzle_structure, &puzzle_combinations);
+            if log10_solutions > log10_target {
+                previous = log10_solutions;
+                continue;
+            }
+
+            let middle_estimate = if previous.is_finite() {
+                (middle_types - 1) as f64 + (previous - log10_target) / (previous - log10_solutions)
+            } else {
+                middle_types as f64
+            };
+            let summaries = strategies
+                .iter()
+                .map(|strategy| {
+                    let search_order =
+                        SearchOrder::from_strategy(&puzzle_structure, strategy.as_ref())?;
+                    let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+                    Ok(SearchSummary::new(&SearchNodes::new(
+                        &puzzle_combinations,
+                        &search_progress,
+                    )))
+                })
+                .collect::<Result<Vec<SearchSummary>, String>>()?;
+            points.push(CriticalPoint {
+                skew: *skew,
+                middle_types,
+                middle_join_counts: puzzle_structure.middle_join_counts.clone(),
+                middle_estimate,
+                log10_solutions,
+                summaries,
+            });
+            break;
+        }
+    }
+    Ok(points)
+}
+
+/// log10 of the expected solutions, which do not depend on the order.
+fn log10_solutions(
+    puzzle_structure: &PuzzleStructure,
+    puzzle_combinations: &impl NodeCombinatorics<Progress = SearchProgress>,
+) -> f64 {
+    puzzle_combinations.log10_nodes(&Used {
+        corners: puzzle_structure.corners,
+        edges: puzzle_structure.edges,
+        interiors: puzzle_structure.interiors,
+        pieces: puzzle_structure
+            .piece_classes
+            .iter()
+            .map(|class| class.pieces)
+            .collect(),
+        borders: puzzle_structure.border_joins,
+        middles: puzzle_structure.middle_joins,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::critical_points;
+    use std::sync::Arc;
+
+    use crate::model::{FastCombinations, SearchOption, SearchOrderStrategy};
+
+    #[test]
+    fn test_critical_points() {
+        let strategies: Vec<Arc<dyn SearchOrderStrategy>> = vec![
+            Arc::new(SearchOption::ScanRows),
+            Arc::new(SearchOption::SpiralIn),
+        ];
+        let points = critical_points(
+            6,
+            6,
+            2,
+            &[1.0, 0.7],
+            0.0,
+            &strategies,
+            FastCombinations::new,
+        )
+        .unwrap();
+        assert_eq!(points.len(), 2);
+        points.iter().for_each(|point| {
+            assert!(point.log10_solutions <= 0.0);
+            assert!(point.middle_estimate > (point.middle_types - 1) as f64);
+            assert!(point.middle_estimate <= point.middle_types as f64);
+            point.summaries.iter().for_each(|summary| {
+                assert!((summary.log10_solutions - point.log10_solutions).abs() < 1e-9);
+            });
+            // Ten solutions are reached with no more types than one.
+            let ten = critical_points(
+                6,
+                6,
+                2,
+                &[point.skew],
+                1.0,
+                &strategies,
+                FastCombinations::new,
+            )
+            .unwrap();
+            assert!(ten[0].middle_estimate < point.middle_estimate);
+        });
+        // Uneven colours match more often, so they need more types.
+        assert!(points[1].middle_types >= points[0].middle_types);
+    }
+}
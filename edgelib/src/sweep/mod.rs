@@ -0,0 +1,5 @@
+mod critical_point;
+mod parameter_sweep;
+
+pub use critical_point::*;
+pub use parameter_sweep::*;
@@ -0,0 +1,241 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use crate::model::{
+    Combinatorics, PuzzleStructure, SearchNodes, SearchOrder, SearchOrderStrategy, SearchProgress,
+    SearchSummary,
+};
+
+/// One puzzle design in a sweep.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepPoint {
+    pub width: usize,
+    pub height: usize,
+    pub border_types: usize,
+    pub middle_types: usize,
+    /// Ratio between the join counts of each edge type and the one before.
+    /// A skew of 1 shares the joins out evenly.
+    pub skew: f64,
+}
+
+/// Summary of each search order at a sweep point, in the order of the sweep's
+/// strategies.
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    pub point: SweepPoint,
+    pub summaries: Vec<SearchSummary>,
+}
+
+/// Every combination of the size, edge type and skew ranges, profiled with
+/// each of the search order strategies.
+pub struct ParameterSweep {
+    pub points: Vec<SweepPoint>,
+    pub strategies: Vec<Arc<dyn SearchOrderStrategy>>,
+}
+
+impl SweepPoint {
+    pub fn structure(&self) -> PuzzleStructure {
+        let mut puzzle_structure = PuzzleStructure::new(
+            self.width,
+            self.height,
+            self.border_types,
+            self.middle_types,
+        );
+        puzzle_structure.border_join_counts =
+            skewed_join_counts(puzzle_structure.border_joins, self.border_types, self.skew);
+        puzzle_structure.middle_join_counts =
+            skewed_join_counts(puzzle_structure.middle_joins, self.middle_types, self.skew);
+        puzzle_structure
+    }
+
+    /// Summary of each search order, with the combinatorics `combinations`
+    /// gives for the point's structure. Fails if a strategy does not give a
+    /// valid order for the structure.
+    pub fn summaries<C: Combinatorics<Progress = SearchProgress>>(
+        &self,
+        strategies: &[Arc<dyn SearchOrderStrategy>],
+        combinations: impl Fn(&PuzzleStructure) -> C,
+    ) -> Result<Vec<SearchSummary>, String> {
+        let puzzle_structure = self.structure();
+        let puzzle_combinations = combinations(&puzzle_structure);
+        strategies
+            .iter()
+            .map(|strategy| {
+                let search_order =
+                    SearchOrder::from_strategy(&puzzle_structure, strategy.as_ref())?;
+                let search_progress = SearchProgress::new(&puzzle_structure, &search_order);
+                Ok(SearchSummary::new(&SearchNodes::new(
+                    &puzzle_combinations,
+                    &search_progress,
+                )))
+            })
+            .collect()
+    }
+}
+
+impl ParameterSweep {
+    pub fn new(
+        widths: RangeInclusive<usize>,
+        heights: RangeInclusive<usize>,
+        border_types: RangeInclusive<usize>,
+        middle_types: RangeInclusive<usize>,
+        skews: &[f64],
+        strategies: &[Arc<dyn SearchOrderStrategy>],
+    ) -> Self {
+        if *widths.start() <= 1 || *heights.start() <= 1 {
+            panic!("Width and height must be greater than 1");
+        }
+        if skews.iter().any(|skew| *skew <= 0.0) {
+            panic!("Skews must be greater than 0");
+        }
+
+        let mut points: Vec<SweepPoint> = vec![];
+        widths.for_each(|width| {
+            heights.clone().for_each(|height| {
+                border_types.clone().for_each(|border_types| {
+                    middle_types.clone().for_each(|middle_types| {
+                        skews.iter().for_each(|skew| {
+                            points.push(SweepPoint {
+                                width,
+                                height,
+                                border_types,
+                                middle_types,
+                                skew: *skew,
+                            })
+                        })
+                    })
+                })
+            })
+        });
+
+        ParameterSweep {
+            points,
+            strategies: strategies.to_vec(),
+        }
+    }
+
+    /// Profiles the points across `threads` threads, returning the results in
+    /// the order of `points`, or the first point's error.
+    pub fn run<C: Combinatorics<Progress = SearchProgress>>(
+        &self,
+        threads: usize,
+        combinations: impl Fn(&PuzzleStructure) -> C + Sync,
+    ) -> Result<Vec<SweepResult>, String> {
+        if threads == 0 {
+            panic!("There must be at least one thread");
+        }
+        let chunk_size = self.points.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .points
+                .chunks(chunk_size)
+                .map(|points| {
+                    let combinations = &combinations;
+                    scope.spawn(move || {
+                        points
+                            .iter()
+                            .map(|point| {
+                                Ok(SweepResult {
+                                    point: point.clone(),
+                                    summaries: point.summaries(&self.strategies, combinations)?,
+                                })
+                            })
+                            .collect::<Vec<Result<SweepResult, String>>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Sweep thread panicked"))
+                .collect()
+        })
+    }
+}
+
+/// Shares `joins` between `types` edge types so that each type has `skew`
+/// times the joins of the one before, give or take rounding. Every type gets
+/// at least one join while there are enough to go round, and a skew of 1
+/// matches the even split of `PuzzleStructure`.
+pub fn skewed_join_counts(joins: usize, types: usize, skew: f64) -> Vec<usize> {
+    if types == 0 {
+        return vec![];
+    }
+    if skew <= 0.0 {
+        panic!("Skew must be greater than 0");
+    }
+
+    let base = if joins >= types { 1 } else { 0 };
+    let spare = joins - base * types;
+    let weights: Vec<f64> = (0..types).map(|index| skew.powi(index as i32)).collect();
+    let total: f64 = weights.iter().sum();
+    let shares: Vec<f64> = weights
+        .iter()
+        .map(|weight| spare as f64 * weight / total)
+        .collect();
+    let mut counts: Vec<usize> = shares.iter().map(|share| share.floor() as usize).collect();
+
+    // Hand what is left after rounding down to the largest remainders.
+    let mut remainders: Vec<usize> = (0..types).collect();
+    remainders.sort_by(|a, b| {
+        (shares[*b] - shares[*b].floor()).total_cmp(&(shares[*a] - shares[*a].floor()))
+    });
+    let left = spare - counts.iter().sum::<usize>();
+    remainders
+        .iter()
+        .take(left)
+        .for_each(|index| counts[*index] += 1);
+
+    counts.iter().map(|count| count + base).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParameterSweep, skewed_join_counts};
+    use std::sync::Arc;
+
+    use crate::model::{FastCombinations, PuzzleStructure, SearchOption, SearchOrderStrategy};
+
+    #[test]
+    fn test_skewed_join_counts() {
+        let puzzle_structure = PuzzleStructure::new(7, 5, 3, 5);
+        assert_eq!(
+            skewed_join_counts(puzzle_structure.middle_joins, 5, 1.0),
+            puzzle_structure.middle_join_counts
+        );
+        assert_eq!(
+            skewed_join_counts(puzzle_structure.border_joins, 3, 1.0),
+            puzzle_structure.border_join_counts
+        );
+        assert_eq!(skewed_join_counts(17, 3, 2.0), vec![3, 5, 9]);
+        assert_eq!(skewed_join_counts(2, 3, 0.5), vec![1, 1, 0]);
+        assert_eq!(skewed_join_counts(40, 4, 0.5).iter().sum::<usize>(), 40);
+    }
+
+    #[test]
+    fn test_sweep() {
+        let strategies: Vec<Arc<dyn SearchOrderStrategy>> = vec![
+            Arc::new(SearchOption::ScanRows),
+            Arc::new(SearchOption::SpiralIn),
+        ];
+        let sweep = ParameterSweep::new(3..=4, 3..=3, 1..=2, 2..=3, &[1.0, 0.5], &strategies);
+        assert_eq!(sweep.points.len(), 16);
+
+        let results = sweep.run(3, FastCombinations::new).unwrap();
+        assert_eq!(results.len(), 16);
+        results
+            .iter()
+            .zip(sweep.points.iter())
+            .for_each(|(result, point)| {
+                assert_eq!(&result.point, point);
+                assert_eq!(
+                    result.summaries,
+                    point.summaries(&strategies, FastCombinations::new).unwrap()
+                );
+            });
+        // The final depth is the same whatever the order.
+        results.iter().for_each(|result| {
+            let solutions = result.summaries[0].log10_solutions;
+            assert!((result.summaries[1].log10_solutions - solutions).abs() < 1e-9);
+        });
+    }
+}